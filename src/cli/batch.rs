@@ -0,0 +1,188 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use container_rack_lib::rack::bom::{bill_of_materials, combine, BomItem};
+use container_rack_lib::rack::geometry::{document_parts, Part};
+use container_rack_lib::rack::material::Material;
+use container_rack_lib::rack::nesting::{nest_parts, sheet_count, SheetSize};
+use container_rack_lib::rack::spec::RackSpec;
+use container_rack_lib::{generate_layout_svg, supported_containers, write_svg};
+use serde::Deserialize;
+
+use super::bom::bom_table;
+use super::generate::{create_file, output_filename};
+
+#[derive(Parser, Debug)]
+pub struct BatchArgs {
+    /// TOML file with `[[racks]]` tables or CSV file with a header, fields are the same as
+    /// options of the generate command, e.g. `container`, `rows`, `columns`, `material_thickness`
+    /// and `output_filename`
+    file: PathBuf,
+
+    /// Directory to save the files to
+    #[arg(short, long, default_value = ".")]
+    output_dir: PathBuf,
+
+    /// Also place parts of all racks on shared sheets, one set of sheets per material
+    #[arg(long)]
+    nest: bool,
+
+    /// Size of the material sheet, e.g. `600x400`
+    #[arg(long, default_value_t = SheetSize::default())]
+    sheet_size: SheetSize,
+
+    /// Overwrite output files if they already exist
+    #[arg(long)]
+    force: bool,
+}
+
+/// Single rack in the batch file
+#[derive(Deserialize, Debug)]
+struct BatchEntry {
+    #[serde(flatten)]
+    spec: RackSpec,
+    output_filename: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct BatchFile {
+    racks: Vec<BatchEntry>,
+}
+
+/// Generate every rack in the batch file and print combined bill of materials
+pub fn generate(args: &BatchArgs, materials: &[Material]) {
+    let entries = match read_entries(&args.file) {
+        Ok(entries) => entries,
+        Err(error) => {
+            println!("Can't read {}: {}", args.file.display(), error);
+            std::process::exit(1);
+        }
+    };
+    if let Err(error) = std::fs::create_dir_all(&args.output_dir) {
+        println!("Can't create {}: {}", args.output_dir.display(), error);
+        std::process::exit(1);
+    }
+    let containers = supported_containers();
+
+    // Parts are grouped by material name, plain thickness is named like `4mm`
+    let mut sheets: BTreeMap<String, (Vec<BomItem>, Vec<Part>)> = BTreeMap::new();
+    for (index, entry) in entries.iter().enumerate() {
+        let spec = &entry.spec;
+        let resolved = spec
+            .material(materials)
+            .and_then(|material| Ok((material, spec.layout(&containers)?)));
+        let (material, layout) = match resolved {
+            Ok(resolved) => resolved,
+            Err(error) => {
+                println!("Rack {} ({}): {}.", index + 1, spec.container, error);
+                std::process::exit(1);
+            }
+        };
+        let svg = match generate_layout_svg(
+            &layout,
+            &material,
+            &spec.primary_color,
+            &spec.secondary_color,
+        ) {
+            Ok(svg) => svg,
+            Err(error) => {
+                println!("Rack {} ({}): {}.", index + 1, spec.container, error);
+                std::process::exit(1);
+            }
+        };
+
+        let group = sheets.entry(material.name.clone()).or_default();
+        group
+            .0
+            .extend(bill_of_materials(&layout, material.measured_thickness));
+        if args.nest {
+            group.1.extend(document_parts(&svg));
+        }
+        let filename = output_filename(entry.output_filename.as_deref(), spec, "svg");
+        save(&args.output_dir.join(filename), &svg, args.force);
+    }
+
+    for (material, (items, parts)) in &sheets {
+        if args.nest {
+            let sheets = match nest_parts(parts, &args.sheet_size) {
+                Ok(sheets) => sheets,
+                Err(error) => {
+                    println!("{}.", error);
+                    std::process::exit(1);
+                }
+            };
+            for (index, sheet) in sheets.iter().enumerate() {
+                let filename = format!("sheet_{}_{}.svg", material, index + 1);
+                save(&args.output_dir.join(filename), sheet, args.force);
+            }
+        }
+
+        println!("Parts in {} material:", material);
+        println!("{}", bom_table(&combine([items.as_slice()])));
+        match sheet_count(items, &args.sheet_size) {
+            Ok(sheets) => println!("Parts fit on {} sheets of {}mm.", sheets, args.sheet_size),
+            Err(error) => println!("{}.", error),
+        }
+    }
+}
+
+fn save(path: &Path, svg: &svg::Document, force: bool) {
+    let result = create_file(&path.to_string_lossy(), force).and_then(|file| write_svg(file, svg));
+    match result {
+        Ok(()) => println!("Saved to {}", path.display()),
+        Err(error) => {
+            println!("Can't write to {}: {}", path.display(), error);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn read_entries(file: &Path) -> Result<Vec<BatchEntry>, String> {
+    let content = std::fs::read_to_string(file).map_err(|error| error.to_string())?;
+    let is_csv = file
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"));
+
+    if is_csv {
+        read_csv(&content)
+    } else {
+        toml::from_str::<BatchFile>(&content)
+            .map(|batch| batch.racks)
+            .map_err(|error| error.to_string())
+    }
+}
+
+/// CSV columns are named as fields of `RackSpec`, lists are quoted, e.g. `"1,1,2"`
+fn read_csv(content: &str) -> Result<Vec<BatchEntry>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+    let headers = reader.headers().map_err(|error| error.to_string())?.clone();
+
+    reader
+        .records()
+        .enumerate()
+        .map(|(index, record)| {
+            let record = record.map_err(|error| error.to_string())?;
+            let mut entry = BatchEntry {
+                spec: RackSpec::default(),
+                output_filename: None,
+            };
+            for (field, value) in headers.iter().zip(record.iter()) {
+                if value.is_empty() {
+                    continue;
+                }
+                if field == "output_filename" {
+                    entry.output_filename = Some(value.to_string());
+                } else {
+                    entry
+                        .spec
+                        .set(field, value)
+                        .map_err(|error| format!("line {}: {}", index + 2, error))?;
+                }
+            }
+            Ok(entry)
+        })
+        .collect()
+}
@@ -0,0 +1,24 @@
+use comfy_table::{Cell, Table};
+use container_rack_lib::rack::bom::BomItem;
+
+/// Bill of materials as a table for CLI
+pub fn bom_table(items: &[BomItem]) -> Table {
+    let mut table = Table::new();
+    table.set_header(vec![
+        Cell::new("Part"),
+        Cell::new("Quantity"),
+        Cell::new("Length (mm)"),
+        Cell::new("Width (mm)"),
+    ]);
+
+    items.iter().for_each(|item| {
+        table.add_row(vec![
+            Cell::new(&item.part),
+            Cell::new(item.quantity),
+            Cell::new(item.length),
+            Cell::new(item.width),
+        ]);
+    });
+
+    table
+}
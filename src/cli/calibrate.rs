@@ -0,0 +1,102 @@
+use clap::Parser;
+use container_rack_lib::rack::calibration::{clearance_steps, generate_calibration_svg};
+use container_rack_lib::rack::material::Material;
+use container_rack_lib::rack::RackOptions;
+use container_rack_lib::write_svg;
+
+use super::generate::create_file;
+
+#[derive(Parser, Debug)]
+pub struct CalibrateArgs {
+    /// Thickness of the plywood or other material
+    #[arg(short, long, required_unless_present = "material")]
+    material_thickness: Option<f32>,
+
+    /// Name of the material preset, see `materials` command
+    #[arg(long, conflicts_with = "material_thickness")]
+    material: Option<String>,
+
+    /// Smallest clearance added to the thickness in mm
+    #[arg(long, default_value_t = -0.2, allow_negative_numbers = true)]
+    from: f32,
+
+    /// Largest clearance added to the thickness in mm
+    #[arg(long, default_value_t = 0.2, allow_negative_numbers = true)]
+    to: f32,
+
+    /// Difference of clearance between two slots in mm
+    #[arg(long, default_value_t = 0.05)]
+    step: f32,
+
+    /// Name of the file to save the SVG to, `.svg` is added when missing
+    #[arg(short, long)]
+    output_filename: Option<String>,
+
+    /// Overwrite the output file if it already exists
+    #[arg(long)]
+    force: bool,
+
+    /// Primary color of the line that will be cut first
+    #[clap(short, long, default_value = "black")]
+    primary_color: String,
+
+    /// Secondary color of the line that will be cut last
+    #[clap(short, long, default_value = "blue")]
+    secondary_color: String,
+
+    /// Color of the engraved clearance labels
+    #[arg(long, default_value_t = RackOptions::default().engrave_color)]
+    engrave_color: String,
+}
+
+/// Generate test-fit coupon for picking the fit clearance of the material
+pub fn coupon(args: &CalibrateArgs, materials: &[Material]) {
+    let material = match (&args.material, args.material_thickness) {
+        (Some(name), _) => match materials.iter().find(|material| material.name == *name) {
+            Some(material) => material.clone(),
+            None => {
+                println!("No material found for '{}'.", name);
+                std::process::exit(1);
+            }
+        },
+        (None, Some(thickness)) if thickness > 0.0 => Material::from(thickness),
+        (None, thickness) => {
+            println!(
+                "Material thickness must be positive, got {}.",
+                thickness.unwrap_or_default()
+            );
+            std::process::exit(1);
+        }
+    };
+    if args.step <= 0.0 || args.to < args.from {
+        println!("Clearances need positive --step and --from not larger than --to.");
+        std::process::exit(1);
+    }
+
+    let clearances = clearance_steps(args.from, args.to, args.step);
+    let svg = generate_calibration_svg(
+        &material,
+        &clearances,
+        &args.primary_color,
+        &args.secondary_color,
+        &args.engrave_color,
+    );
+
+    let filename = match &args.output_filename {
+        Some(name) if name.to_lowercase().ends_with(".svg") => name.clone(),
+        Some(name) => format!("{}.svg", name),
+        None => format!("calibration_{}.svg", material.name),
+    };
+    match create_file(&filename, args.force).and_then(|file| write_svg(file, &svg)) {
+        Ok(()) => println!(
+            "Saved {} slots for {} to {}",
+            clearances.len(),
+            material,
+            filename
+        ),
+        Err(error) => {
+            println!("Can't write to {}: {}", filename, error);
+            std::process::exit(1);
+        }
+    }
+}
@@ -1,18 +1,223 @@
+use clap::{Parser, Subcommand, ValueEnum};
 use comfy_table::{Cell, Table};
 use container_rack_lib::rack::Container;
 use container_rack_lib::supported_containers;
+use serde::Serialize;
+
+#[derive(Parser, Debug)]
+pub struct ContainersArgs {
+    #[command(subcommand)]
+    command: Option<ContainersCommand>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table, global = true)]
+    format: OutputFormat,
+
+    /// Show only containers from this vendor
+    #[arg(long)]
+    vendor: Option<String>,
+
+    /// Show only containers at most this wide in mm
+    #[arg(long)]
+    max_width: Option<usize>,
+
+    /// Show only containers at most this deep in mm
+    #[arg(long)]
+    max_depth: Option<usize>,
+
+    /// Show only containers at most this tall in mm
+    #[arg(long)]
+    max_height: Option<usize>,
+}
+
+#[derive(Subcommand, Debug)]
+enum ContainersCommand {
+    /// Show all details of one container
+    Show {
+        /// Key of container
+        key: String,
+    },
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+enum OutputFormat {
+    Table,
+    Json,
+    Yaml,
+    Csv,
+}
+
+/// Container with its key, as written to machine-readable formats
+#[derive(Serialize)]
+pub struct ContainerDetails<'a> {
+    key: String,
+    #[serde(flatten)]
+    container: &'a Container,
+}
+
+impl<'a> From<&'a Container> for ContainerDetails<'a> {
+    fn from(container: &'a Container) -> Self {
+        ContainerDetails {
+            key: container.key(),
+            container,
+        }
+    }
+}
+
+impl ContainersArgs {
+    fn matches(&self, container: &Container) -> bool {
+        let dimensions = &container.dimensions;
+
+        self.vendor
+            .as_ref()
+            .is_none_or(|vendor| container.vendor.eq_ignore_ascii_case(vendor))
+            && self.max_width.is_none_or(|width| dimensions.width <= width)
+            && self.max_depth.is_none_or(|depth| dimensions.depth <= depth)
+            && self
+                .max_height
+                .is_none_or(|height| dimensions.height <= height)
+    }
+}
 
 /// Print containers to CLI
-pub fn print_containers() {
+pub fn print_containers(args: &ContainersArgs) {
     let containers = supported_containers();
-    let mut table = Table::new();
-    table.set_header(table_header());
 
-    containers.into_iter().for_each(|container| {
-        table.add_row(container.to_table_row());
-    });
+    if let Some(ContainersCommand::Show { key }) = &args.command {
+        match containers.iter().find(|container| container.key() == *key) {
+            Some(container) => print_container(container, args.format),
+            None => {
+                println!("No supported containers found.");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let containers = containers
+        .iter()
+        .filter(|container| args.matches(container))
+        .collect::<Vec<&Container>>();
 
-    println!("{table}");
+    match args.format {
+        OutputFormat::Table => {
+            let mut table = Table::new();
+            table.set_header(table_header());
+            containers.iter().for_each(|container| {
+                table.add_row(container.to_table_row());
+            });
+            println!("{table}");
+        }
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&details(&containers)).unwrap()
+        ),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(&details(&containers)).unwrap()),
+        OutputFormat::Csv => print_csv(&containers),
+    }
+}
+
+/// Print single container with all of its details
+fn print_container(container: &Container, format: OutputFormat) {
+    match format {
+        OutputFormat::Table => {
+            let dimensions = &container.dimensions;
+            let mut table = Table::new();
+            table.set_header(vec![Cell::new("Field"), Cell::new("Value")]);
+            [
+                ("Key", container.key()),
+                ("Brand", container.vendor.clone()),
+                ("Model", container.model.clone()),
+                ("Description", container.description.clone()),
+                ("Group", container.compatibility_group.clone()),
+                ("Width (mm)", dimensions.width.to_string()),
+                ("Depth (mm)", dimensions.depth.to_string()),
+                ("Height (mm)", dimensions.height.to_string()),
+                (
+                    "Side wing from box top (mm)",
+                    dimensions.side_wing_from_box_top.to_string(),
+                ),
+                (
+                    "Side wing width (mm)",
+                    dimensions.side_wing_width.to_string(),
+                ),
+            ]
+            .into_iter()
+            .for_each(|(field, value)| {
+                table.add_row(vec![Cell::new(field), Cell::new(value)]);
+            });
+            container.links.iter().for_each(|link| {
+                table.add_row(vec![
+                    Cell::new("Link"),
+                    Cell::new(format!("{}\n{}", link.title, link.url)),
+                ]);
+            });
+            println!("{table}");
+        }
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&ContainerDetails::from(container)).unwrap()
+        ),
+        OutputFormat::Yaml => print!(
+            "{}",
+            serde_yaml::to_string(&ContainerDetails::from(container)).unwrap()
+        ),
+        OutputFormat::Csv => print_csv(&[container]),
+    }
+}
+
+pub fn details<'a>(containers: &[&'a Container]) -> Vec<ContainerDetails<'a>> {
+    containers
+        .iter()
+        .map(|container| ContainerDetails::from(*container))
+        .collect()
+}
+
+fn print_csv(containers: &[&Container]) {
+    write_csv(std::io::stdout(), containers).unwrap();
+}
+
+/// CSV can't hold nested data, so dimensions get own columns and links are joined with spaces
+fn write_csv(output: impl std::io::Write, containers: &[&Container]) -> csv::Result<()> {
+    let mut writer = csv::Writer::from_writer(output);
+    writer.write_record([
+        "key",
+        "vendor",
+        "model",
+        "description",
+        "compatibility_group",
+        "width",
+        "depth",
+        "height",
+        "side_wing_from_box_top",
+        "side_wing_width",
+        "links",
+    ])?;
+
+    for container in containers {
+        let dimensions = &container.dimensions;
+        writer.write_record([
+            container.key(),
+            container.vendor.clone(),
+            container.model.clone(),
+            container.description.clone(),
+            container.compatibility_group.clone(),
+            dimensions.width.to_string(),
+            dimensions.depth.to_string(),
+            dimensions.height.to_string(),
+            dimensions.side_wing_from_box_top.to_string(),
+            dimensions.side_wing_width.to_string(),
+            container
+                .links
+                .iter()
+                .map(|link| link.url.to_string())
+                .collect::<Vec<String>>()
+                .join(" "),
+        ])?;
+    }
+
+    writer.flush()?;
+    Ok(())
 }
 
 fn table_header() -> Vec<Cell> {
@@ -20,6 +225,8 @@ fn table_header() -> Vec<Cell> {
         Cell::new("Key"),
         Cell::new("Brand"),
         Cell::new("Model"),
+        Cell::new("Size (WxDxH mm)"),
+        Cell::new("Group"),
         Cell::new("Description"),
         Cell::new("Links"),
     ]
@@ -32,13 +239,17 @@ trait ToTableRow {
 impl ToTableRow for Container {
     fn to_table_row(&self) -> Vec<Cell> {
         vec![
-            Cell::new(&self.key()),
+            Cell::new(self.key()),
             Cell::new(&self.vendor),
             Cell::new(&self.model),
+            Cell::new(format!(
+                "{}x{}x{}",
+                self.dimensions.width, self.dimensions.depth, self.dimensions.height
+            )),
+            Cell::new(&self.compatibility_group),
             Cell::new(&self.description).truncate(40),
             Cell::new(
-                &self
-                    .links
+                self.links
                     .iter()
                     .map(|link| link.url.to_string())
                     .collect::<Vec<String>>()
@@ -91,3 +302,75 @@ impl TruncateWords for &str {
         chunks
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matching(args: &[&str]) -> Vec<String> {
+        let args = ContainersArgs::parse_from([&["containers"], args].concat());
+        supported_containers()
+            .iter()
+            .filter(|container| args.matches(container))
+            .map(Container::key)
+            .collect()
+    }
+
+    #[test]
+    fn filters_by_vendor_ignoring_case() {
+        assert_eq!(
+            matching(&["--vendor", "ikea"]),
+            vec!["ikea-samla_11_l", "ikea-samla_22_l"]
+        );
+        assert_eq!(matching(&[]).len(), supported_containers().len());
+    }
+
+    #[test]
+    fn filters_by_largest_size() {
+        assert_eq!(
+            matching(&["--vendor", "smartstore", "--max-height", "56"]),
+            vec!["smartstore-classic_1", "smartstore-home_1"]
+        );
+        assert!(matching(&["--max-depth", "300"])
+            .iter()
+            .all(|key| key.starts_with("smartstore")));
+        assert!(matching(&["--max-width", "100"]).is_empty());
+    }
+
+    #[test]
+    fn format_is_accepted_after_show() {
+        let args = ContainersArgs::parse_from([
+            "containers",
+            "show",
+            "ikea-samla_11_l",
+            "--format",
+            "yaml",
+        ]);
+        assert_eq!(args.format, OutputFormat::Yaml);
+        assert!(ContainersArgs::try_parse_from(["containers", "--format", "xml"]).is_err());
+    }
+
+    #[test]
+    fn json_has_the_key_next_to_the_container_fields() {
+        let containers = supported_containers();
+        let json = serde_json::to_value(details(&[&containers[0]])).unwrap();
+        assert_eq!(json[0]["key"], "smartstore-classic_1");
+        assert_eq!(json[0]["vendor"], "SmartStore");
+        assert_eq!(json[0]["dimensions"]["side_wing_from_box_top"], 15);
+        assert!(json[0]["links"][0]["url"].is_string());
+    }
+
+    #[test]
+    fn csv_has_a_column_per_dimension() {
+        let containers = supported_containers();
+        let mut output = vec![];
+        write_csv(&mut output, &[&containers[0]]).unwrap();
+
+        let csv = String::from_utf8(output).unwrap();
+        let lines = csv.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("key,vendor,model,description,compatibility_group,width"));
+        assert!(lines[1].starts_with("smartstore-classic_1,SmartStore,Classic 1,"));
+        assert!(lines[1].contains(",170,210,56,15,8,https://"));
+    }
+}
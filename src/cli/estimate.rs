@@ -0,0 +1,98 @@
+use comfy_table::{Cell, Table};
+use container_rack_lib::rack::estimate::Estimate;
+
+/// Cut length and time per layer as a table for CLI, last row has the totals
+pub fn estimate_table(estimate: &Estimate) -> Table {
+    let mut table = Table::new();
+    table.set_header(vec![
+        Cell::new("Layer"),
+        Cell::new("Contours"),
+        Cell::new("Cut length (m)"),
+        Cell::new("Time"),
+    ]);
+
+    estimate.layers.iter().for_each(|layer| {
+        table.add_row(vec![
+            Cell::new(&layer.layer),
+            Cell::new(layer.contours),
+            Cell::new(format!("{:.2}", layer.length / 1000.0)),
+            Cell::new(duration(layer.time)),
+        ]);
+    });
+    table.add_row(vec![
+        Cell::new("Total"),
+        Cell::new(
+            estimate
+                .layers
+                .iter()
+                .map(|layer| layer.contours)
+                .sum::<usize>(),
+        ),
+        Cell::new(format!("{:.2}", estimate.cut_length() / 1000.0)),
+        Cell::new(duration(estimate.cutting_time())),
+    ]);
+
+    table
+}
+
+/// Material used and its price, materials without price say so instead of costing 0
+pub fn material_summary(estimate: &Estimate) -> String {
+    let cost = if estimate.material_cost > 0.0 {
+        format!("cost {:.2}", estimate.material_cost)
+    } else {
+        "no price set".to_string()
+    };
+    format!(
+        "Parts use {:.3}m² of material, {} sheets of {}mm, {}.",
+        estimate.material_area / 1_000_000.0,
+        estimate.sheets,
+        estimate.sheet_size,
+        cost
+    )
+}
+
+/// Seconds as `1h 02m 03s`
+fn duration(seconds: f32) -> String {
+    let seconds = seconds.round() as u64;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+
+    if hours > 0 {
+        format!("{}h {:02}m {:02}s", hours, minutes, seconds)
+    } else {
+        format!("{}m {:02}s", minutes, seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use container_rack_lib::rack::nesting::SheetSize;
+
+    fn estimate(material_cost: f32) -> Estimate {
+        Estimate {
+            layers: vec![],
+            material_area: 250_000.0,
+            sheets: 2,
+            sheet_size: SheetSize::default(),
+            material_cost,
+        }
+    }
+
+    #[test]
+    fn summary_tells_when_material_has_no_price() {
+        assert_eq!(
+            material_summary(&estimate(0.0)),
+            "Parts use 0.250m² of material, 2 sheets of 600x400mm, no price set."
+        );
+        assert_eq!(
+            material_summary(&estimate(9.6)),
+            "Parts use 0.250m² of material, 2 sheets of 600x400mm, cost 9.60."
+        );
+    }
+
+    #[test]
+    fn duration_has_hours_only_when_needed() {
+        assert_eq!(duration(62.4), "1m 02s");
+        assert_eq!(duration(3723.0), "1h 02m 03s");
+    }
+}
@@ -1,29 +1,172 @@
-use clap::Parser;
-use container_rack_lib::{generate_svg, supported_containers};
+use std::fs::File;
+use std::io::{self, Write};
+
+use clap::{Args, Parser, ValueEnum};
+use container_rack_lib::rack::bom::bill_of_materials;
+use container_rack_lib::rack::estimate::{estimate, LaserProfile};
+use container_rack_lib::rack::gcode::GcodeSettings;
+use container_rack_lib::rack::joint::{CornerJoint, MetricScrew};
+use container_rack_lib::rack::label::Labels;
+use container_rack_lib::rack::material::Material;
+use container_rack_lib::rack::mount::WallMount;
+use container_rack_lib::rack::nesting::{sheet_count, SheetSize};
+use container_rack_lib::rack::relief::ReliefStyle;
+use container_rack_lib::rack::retention::RetentionBars;
+use container_rack_lib::rack::spec::{RackSpec, RowSpec};
+use container_rack_lib::rack::stop::DrawerStop;
+use container_rack_lib::rack::toolpath::{optimize_toolpath, travel_distance};
+use container_rack_lib::rack::RackOptions;
+use container_rack_lib::{
+    generate_layout_svg, render_dxf, render_gcode, render_svg, supported_containers,
+};
+
+use super::bom::bom_table;
+use super::estimate::{estimate_table, material_summary};
+use super::interactive::{ask_spec, confirm};
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 pub struct RackGenerationArgs {
     /// Number of rows of boxes
-    #[arg(short, long)]
-    rows: usize,
+    #[arg(short, long, required_unless_present_any = ["rows_spec", "interactive"])]
+    rows: Option<usize>,
+
+    /// Height of each row from top to bottom, separated by commas. Plain number is
+    /// a multiple of the container height (e.g. `1,1,2,3`), number with `mm` suffix is
+    /// an exact height (e.g. `56mm,120mm`) and a container key uses that container for the row
+    /// (e.g. `smartstore-classic_1,smartstore-classic_3`)
+    #[arg(long, value_delimiter = ',', conflicts_with = "rows")]
+    rows_spec: Option<Vec<RowSpec>>,
 
     /// Number columns of boxes
-    #[arg(short, long)]
-    columns: usize,
+    #[arg(short, long, required_unless_present_any = ["column_widths", "interactive"])]
+    columns: Option<usize>,
+
+    /// Width of the container in each column from left to right in mm, separated by commas.
+    /// Defaults to the width of the selected container
+    #[arg(long, value_delimiter = ',', conflicts_with = "columns")]
+    column_widths: Option<Vec<usize>>,
 
     /// Thickness of the plywood or other material
-    #[arg(short, long)]
-    material_thickness: f32,
+    #[arg(short, long, required_unless_present_any = ["interactive", "material"])]
+    material_thickness: Option<f32>,
+
+    /// Name of the material preset with measured thickness, kerf and fit clearance, see
+    /// `materials` command
+    #[arg(long, conflicts_with = "material_thickness")]
+    material: Option<String>,
 
     /// Key of container
+    #[arg(long, required_unless_present = "interactive")]
+    container: Option<String>,
+
+    /// Ask container, rows, columns and thickness one by one, showing the size of the rack
+    #[arg(short, long)]
+    interactive: bool,
+
+    /// Size of the material sheet for counting sheets needed, e.g. `600x400`
+    #[arg(long, default_value_t = SheetSize::default())]
+    sheet_size: SheetSize,
+
+    /// Stack side wings so neighbouring wings share one cut line, saves material and laser time
+    #[arg(long)]
+    common_line: bool,
+
+    /// Reorder cuts to shorten laser moves between them, holes are still cut before outlines
     #[arg(long)]
-    container: String,
+    optimize_toolpath: bool,
 
-    /// Name of the file to save the SVG to
+    /// Print cut length per layer, cutting time, material used and its cost
+    #[arg(long)]
+    estimate: bool,
+
+    /// Price of one square meter of material for the estimate, defaults to the price of the
+    /// material preset
+    #[arg(long)]
+    price_per_square_meter: Option<f32>,
+
+    /// Cutting speed of the laser in mm/s for the estimate
+    #[arg(long, default_value_t = LaserProfile::default().cut_speed)]
+    cut_speed: f32,
+
+    /// Cutting speed of single layer in mm/s for the estimate, e.g. `black=8,blue=12`
+    #[arg(long, value_delimiter = ',', value_parser = parse_layer_speed)]
+    layer_speed: Vec<(String, f32)>,
+
+    /// Seconds needed to pierce the material at the start of every cut, for the estimate
+    #[arg(long, default_value_t = LaserProfile::default().pierce_time)]
+    pierce_time: f32,
+
+    /// Output format, G-code is for CNC routers
+    #[arg(long, value_enum, default_value_t = OutputFormat::Svg)]
+    format: OutputFormat,
+
+    /// Add `dogbone` or `t-bone` relief to the corners of the slots for cutting with a router bit
+    #[arg(long)]
+    relief: Option<ReliefStyle>,
+
+    /// Diameter of the router bit in mm, sizes the reliefs and offsets the G-code toolpath
+    #[arg(long, default_value_t = RackOptions::default().tool_diameter)]
+    tool_diameter: f32,
+
+    /// Joint between side panels and covers, `taps` near the front and back, `fingers` along
+    /// the full depth, `snap-fit` taps that hold without glue, `t-slot` taps with a screw
+    /// and a captive nut, so the rack comes apart, or `dovetail` fingers wider at the tip.
+    /// Count of the fingers follows from the depth and thickness
+    #[arg(long, default_value_t = CornerJoint::default())]
+    corner_joint: CornerJoint,
+
+    /// Metric screw of the T-slot joints, `m3`, `m4` or `m5`. Sizes the bolt holes in the
+    /// covers and the nut pockets in the side panels
+    #[arg(long, default_value_t = MetricScrew::default())]
+    screw: MetricScrew,
+
+    /// Add keyhole slots for screwing the rack to a wall, in a `back-stretcher` standing on
+    /// the top cover or in the outer `side-panels`
+    #[arg(long)]
+    wall_mount: Option<WallMount>,
+
+    /// Add holes to the covers and pegs for them, so stacked racks with the same columns
+    /// register
+    #[arg(long)]
+    stacking: bool,
+
+    /// Add a drawer stop at the back end of the side wings, so containers don't slide out:
+    /// `tab` standing up in a slot of the wing or `notch` cut into its free edge
+    #[arg(long)]
+    drawer_stop: Option<DrawerStop>,
+
+    /// How far the tab of the drawer stop stands above the wing or the notch cuts into it in mm
+    #[arg(long, default_value_t = RackOptions::default().drawer_stop_depth)]
+    drawer_stop_depth: f32,
+
+    /// Van mode, add removable retention bars across the front of the `rows` or one for every
+    /// slot with `columns`. Bars drop into slots in the front edge of the side panels
+    #[arg(long)]
+    retention_bars: Option<RetentionBars>,
+
+    /// CSV file with `row`, `column` and `label` columns, counted from 1 from the top left.
+    /// Every labelled slot gets a holder hanging on the front of the side panel, with the
+    /// label engraved on it
+    #[arg(long)]
+    labels: Option<String>,
+
+    /// Color of the engraved text, e.g. labels
+    #[arg(long, default_value_t = RackOptions::default().engrave_color)]
+    engrave_color: String,
+
+    #[command(flatten)]
+    cnc: CncArgs,
+
+    /// Name of the file to save to, extension of the format is added when missing. Use `-` to
+    /// write to stdout
     #[arg(short, long)]
     output_filename: Option<String>,
 
+    /// Overwrite the output file if it already exists
+    #[arg(long)]
+    force: bool,
+
     /// Primary color of the line that will be cut first
     #[clap(short, long, default_value = "black")]
     primary_color: String,
@@ -33,45 +176,380 @@ pub struct RackGenerationArgs {
     secondary_color: String,
 }
 
+/// Router settings used with `--format gcode`
+#[derive(Args, Debug)]
+#[command(next_help_heading = "G-code")]
+struct CncArgs {
+    /// Depth of a single pass in mm
+    #[arg(long, default_value_t = GcodeSettings::default().depth_per_pass)]
+    depth_per_pass: f32,
+
+    /// Feed rate in mm/min
+    #[arg(long, default_value_t = GcodeSettings::default().feed_rate)]
+    feed_rate: f32,
+
+    /// Plunge rate in mm/min
+    #[arg(long, default_value_t = GcodeSettings::default().plunge_rate)]
+    plunge_rate: f32,
+
+    /// Spindle speed in RPM
+    #[arg(long, default_value_t = GcodeSettings::default().spindle_speed)]
+    spindle_speed: u32,
+
+    /// Height above the material for moves between cuts in mm
+    #[arg(long, default_value_t = GcodeSettings::default().safe_height)]
+    safe_height: f32,
+
+    /// Number of tabs holding each part in the sheet, 0 for none
+    #[arg(long, default_value_t = GcodeSettings::default().tabs)]
+    tabs: usize,
+
+    /// Width of a tab in mm
+    #[arg(long, default_value_t = GcodeSettings::default().tab_width)]
+    tab_width: f32,
+
+    /// Height of a tab in mm
+    #[arg(long, default_value_t = GcodeSettings::default().tab_height)]
+    tab_height: f32,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+enum OutputFormat {
+    Svg,
+    Dxf,
+    Gcode,
+}
+
+impl OutputFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Svg => "svg",
+            OutputFormat::Dxf => "dxf",
+            OutputFormat::Gcode => "gcode",
+        }
+    }
+}
+
+impl RackGenerationArgs {
+    fn gcode_settings(&self, material: &Material) -> GcodeSettings {
+        GcodeSettings {
+            tool_diameter: self.tool_diameter,
+            cut_depth: material.measured_thickness,
+            depth_per_pass: self.cnc.depth_per_pass,
+            feed_rate: self.cnc.feed_rate,
+            plunge_rate: self.cnc.plunge_rate,
+            spindle_speed: self.cnc.spindle_speed,
+            safe_height: self.cnc.safe_height,
+            tabs: self.cnc.tabs,
+            tab_width: self.cnc.tab_width,
+            tab_height: self.cnc.tab_height,
+        }
+    }
+
+    fn laser_profile(&self) -> LaserProfile {
+        LaserProfile {
+            cut_speed: self.cut_speed,
+            layer_speeds: self.layer_speed.iter().cloned().collect(),
+            pierce_time: self.pierce_time,
+        }
+    }
+
+    fn spec(&self) -> RackSpec {
+        RackSpec {
+            container: self.container.clone().unwrap_or_default(),
+            rows: self.rows.unwrap_or_default(),
+            rows_spec: self.rows_spec.clone().unwrap_or_default(),
+            columns: self.columns.unwrap_or_default(),
+            column_widths: self.column_widths.clone().unwrap_or_default(),
+            material_thickness: self.material_thickness.unwrap_or_default(),
+            material: self.material.clone().unwrap_or_default(),
+            primary_color: self.primary_color.clone(),
+            secondary_color: self.secondary_color.clone(),
+            options: RackOptions {
+                common_line: self.common_line,
+                relief: self.relief,
+                tool_diameter: self.tool_diameter,
+                corner_joint: self.corner_joint,
+                screw: self.screw,
+                wall_mount: self.wall_mount,
+                stacking: self.stacking,
+                engrave_color: self.engrave_color.clone(),
+                drawer_stop: self.drawer_stop,
+                drawer_stop_depth: self.drawer_stop_depth,
+                retention_bars: self.retention_bars,
+            },
+        }
+    }
+}
+
 /// Generate SVG for the container rack
-pub fn svg(args: &RackGenerationArgs) {
-    println!(
+pub fn svg(args: &RackGenerationArgs, materials: &[Material]) {
+    let to_stdout = args.output_filename.as_deref() == Some("-");
+    let spec = if args.interactive {
+        let spec = ask_spec(
+            args.spec(),
+            &supported_containers(),
+            materials,
+            &args.sheet_size,
+        );
+        if !confirm("Write the file?") {
+            return;
+        }
+        spec
+    } else {
+        args.spec()
+    };
+    // When SVG goes to stdout, everything meant for humans goes to stderr
+    let info = |message: String| {
+        if to_stdout {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    };
+
+    let mut material = match spec.material(materials) {
+        Ok(material) => material,
+        Err(error) => {
+            info(format!("{}.", error));
+            std::process::exit(1);
+        }
+    };
+    if args.format == OutputFormat::Gcode {
+        // Toolpath is offset by the radius of the bit instead
+        material.kerf = 0.0;
+        if spec.options.common_line {
+            info(
+                "Common line can't be cut with G-code, shared lines would be cut without \
+                  the offset of the bit. Leave out --common-line."
+                    .to_string(),
+            );
+            std::process::exit(1);
+        }
+    }
+
+    info(format!(
         "So you want to generate organizer with {} rows and {} columns, using {}mm thick material.",
-        args.rows, args.columns, args.material_thickness
-    );
-    let supported_containers = supported_containers();
+        spec.rows_spec().len(),
+        spec.columns(),
+        material.measured_thickness
+    ));
 
-    let container = match supported_containers
-        .iter()
-        .find(|c| c.key() == args.container)
-    {
-        Some(container) => container,
-        None => {
-            println!("No supported containers found.");
+    let mut layout = match spec.layout(&supported_containers()) {
+        Ok(layout) => layout,
+        Err(error) => {
+            info(format!("{}.", error));
             //exit from process
             std::process::exit(1);
         }
     };
+    if let Some(file) = &args.labels {
+        layout.labels = match read_labels(file) {
+            Ok(labels) => labels,
+            Err(error) => {
+                info(format!("Can't read labels from {}: {}.", file, error));
+                std::process::exit(1);
+            }
+        };
+        let columns = layout.column_widths.len();
+        for (row, column) in layout.labels.keys() {
+            if *row >= layout.rows.len() || *column >= columns {
+                info(format!(
+                    "Warning: rack has no slot in row {} and column {}, its label is skipped.",
+                    row + 1,
+                    column + 1
+                ));
+            }
+        }
+    }
+
+    let mut svg = match generate_layout_svg(
+        &layout,
+        &material,
+        &spec.primary_color,
+        &spec.secondary_color,
+    ) {
+        Ok(svg) => svg,
+        Err(error) => {
+            info(format!("{}.", error));
+            std::process::exit(1);
+        }
+    };
+    if args.optimize_toolpath {
+        let travel_before = travel_distance(&svg);
+        svg = optimize_toolpath(&svg);
+        info(format!(
+            "Laser travel between cuts reduced from {:.0}mm to {:.0}mm.",
+            travel_before,
+            travel_distance(&svg)
+        ));
+    }
+
+    let content = match args.format {
+        OutputFormat::Svg => render_svg(&svg),
+        OutputFormat::Dxf => render_dxf(&svg),
+        OutputFormat::Gcode => match render_gcode(&svg, &args.gcode_settings(&material)) {
+            Ok(gcode) => gcode,
+            Err(error) => {
+                info(format!("{}.", error));
+                std::process::exit(1);
+            }
+        },
+    };
+    if to_stdout {
+        match io::stdout().lock().write_all(content.as_bytes()) {
+            // Reader closing the pipe early is not an error
+            Err(error) if error.kind() != io::ErrorKind::BrokenPipe => {
+                info(format!("Can't write to stdout: {}", error));
+                std::process::exit(1);
+            }
+            _ => {}
+        }
+    } else {
+        let filename = output_filename(
+            args.output_filename.as_deref(),
+            &spec,
+            args.format.extension(),
+        );
+        match create_file(&filename, args.force) {
+            Ok(mut file) => {
+                file.write_all(content.as_bytes()).unwrap();
+                info(format!("Saved to {}", &filename));
+            }
+            Err(error) => {
+                info(format!("Can't write to {}: {}", &filename, error));
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let items = bill_of_materials(&layout, material.measured_thickness);
+    info(bom_table(&items).to_string());
+    match sheet_count(&items, &args.sheet_size) {
+        Ok(sheets) => info(format!(
+            "Parts fit on {} sheets of {}mm.",
+            sheets, args.sheet_size
+        )),
+        Err(error) => info(format!("{}.", error)),
+    }
+
+    if args.estimate {
+        let price = args
+            .price_per_square_meter
+            .unwrap_or(material.price_per_square_meter);
+        match estimate(&svg, &args.sheet_size, price, &args.laser_profile()) {
+            Ok(estimate) => {
+                info(estimate_table(&estimate).to_string());
+                info(material_summary(&estimate));
+            }
+            Err(error) => info(format!("{}.", error)),
+        }
+    }
+}
+
+/// Parse layer speed like `black=8`
+fn parse_layer_speed(value: &str) -> Result<(String, f32), String> {
+    let (layer, speed) = value
+        .split_once('=')
+        .ok_or(format!("Expected layer=speed, got '{}'", value))?;
+    match speed.trim().parse::<f32>() {
+        Ok(speed) if speed > 0.0 => Ok((layer.trim().to_string(), speed)),
+        _ => Err(format!("Invalid speed '{}'", speed)),
+    }
+}
 
-    let svg = generate_svg(
-        args.rows,
-        args.columns,
-        args.material_thickness,
-        &container,
-        &args.primary_color,
-        &args.secondary_color,
-    );
-    let filename = match args.output_filename.clone() {
-        Some(name) => name,
-        None => format!(
-            "organizer_{}_rows_{}_columns_{}mm_thick_{}",
-            args.rows,
-            args.columns,
-            args.material_thickness,
-            container.key()
-        ),
+/// Name of the output file with the extension, e.g. `svg`
+pub fn output_filename(output_filename: Option<&str>, spec: &RackSpec, extension: &str) -> String {
+    let filename = match output_filename {
+        Some(name) => name.to_string(),
+        None => spec.default_filename(),
     };
-    let filename_with_extension = format!("{}.svg", filename);
-    svg::save(&filename_with_extension, &svg).unwrap();
-    println!("Saved to {}", &filename_with_extension);
+
+    if filename
+        .to_lowercase()
+        .ends_with(&format!(".{}", extension))
+    {
+        filename
+    } else {
+        format!("{}.{}", filename, extension)
+    }
+}
+
+/// Create output file, existing file is overwritten only when forced
+pub fn create_file(filename: &str, force: bool) -> io::Result<File> {
+    let mut options = File::options();
+    options.write(true);
+    if force {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+
+    options.open(filename).map_err(|error| {
+        if error.kind() == io::ErrorKind::AlreadyExists {
+            io::Error::new(
+                error.kind(),
+                "file already exists, use --force to overwrite it",
+            )
+        } else {
+            error
+        }
+    })
+}
+
+/// Labels from CSV file with `row`, `column` and `label` columns
+fn read_labels(file: &str) -> Result<Labels, String> {
+    let content = std::fs::read_to_string(file).map_err(|error| error.to_string())?;
+    parse_labels(&content)
+}
+
+/// Labels from CSV content, rows and columns are counted from 1
+fn parse_labels(content: &str) -> Result<Labels, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+
+    let mut labels = Labels::new();
+    for (index, record) in reader.deserialize::<LabelRecord>().enumerate() {
+        let record = record.map_err(|error| format!("line {}: {}", index + 2, error))?;
+        if record.row == 0 || record.column == 0 {
+            return Err(format!(
+                "line {}: rows and columns are counted from 1",
+                index + 2
+            ));
+        }
+        labels.insert((record.row - 1, record.column - 1), record.label);
+    }
+    Ok(labels)
+}
+
+#[derive(serde::Deserialize)]
+struct LabelRecord {
+    row: usize,
+    column: usize,
+    label: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_are_counted_from_one() {
+        let labels = parse_labels("row,column,label\n1, 1, Screws\n3,2,\"M3, M4\"\n").unwrap();
+
+        assert_eq!(labels.len(), 2);
+        assert_eq!(labels[&(0, 0)], "Screws");
+        assert_eq!(labels[&(2, 1)], "M3, M4");
+    }
+
+    #[test]
+    fn labels_reject_zero_and_missing_columns() {
+        assert_eq!(
+            parse_labels("row,column,label\n1,1,A\n0,1,B\n").unwrap_err(),
+            "line 3: rows and columns are counted from 1"
+        );
+        assert!(parse_labels("row,label\n1,A\n").is_err());
+        assert!(parse_labels("row,column,label\nfirst,1,A\n").is_err());
+    }
 }
@@ -0,0 +1,228 @@
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use container_rack_lib::rack::bom::bill_of_materials;
+use container_rack_lib::rack::material::Material;
+use container_rack_lib::rack::nesting::{sheet_count, SheetSize};
+use container_rack_lib::rack::spec::{RackSpec, RowSpec};
+use container_rack_lib::rack::Container;
+
+/// Ask rack options one by one, showing the size of the rack after each answer.
+///
+/// Questions and summaries go to stderr, so the SVG can still be written to stdout.
+///
+/// Values already in `spec` are offered as defaults.
+pub fn ask_spec(
+    mut spec: RackSpec,
+    containers: &[Container],
+    materials: &[Material],
+    sheet: &SheetSize,
+) -> RackSpec {
+    eprintln!("Supported containers:");
+    containers
+        .iter()
+        .enumerate()
+        .for_each(|(index, container)| {
+            let dimensions = &container.dimensions;
+            eprintln!(
+                "  {}) {} - {} {} ({}x{}x{}mm)",
+                index + 1,
+                container.key(),
+                container.vendor,
+                container.model,
+                dimensions.width,
+                dimensions.depth,
+                dimensions.height
+            );
+        });
+    if spec.container.is_empty() {
+        spec.container = containers[0].key();
+    }
+    spec.container = ask(
+        "Container, number or key",
+        &spec.container.clone(),
+        |answer| match answer.parse::<usize>() {
+            Ok(number) if number >= 1 && number <= containers.len() => {
+                Ok(containers[number - 1].key())
+            }
+            _ => containers
+                .iter()
+                .find(|container| container.key() == answer)
+                .map(|container| container.key())
+                .ok_or(format!("No supported container found for '{}'", answer)),
+        },
+    );
+    print_summary(&spec, containers, materials, sheet);
+
+    let rows = if spec.rows_spec.is_empty() {
+        spec.rows.max(1).to_string()
+    } else {
+        join(&spec.rows_spec)
+    };
+    (spec.rows, spec.rows_spec) =
+        ask(
+            "Rows, number or heights like 1,1,2,3",
+            &rows,
+            |answer| match answer.parse::<usize>() {
+                Ok(0) => Err("Rack needs at least one row".to_string()),
+                Ok(rows) => Ok((rows, vec![])),
+                Err(_) => {
+                    let rows_spec = parse_list::<RowSpec>(answer)?;
+                    match rows_spec.iter().find_map(|row| match row {
+                        RowSpec::Container(key) if !containers.iter().any(|c| c.key() == *key) => {
+                            Some(key)
+                        }
+                        _ => None,
+                    }) {
+                        Some(key) => Err(format!("No supported container found for '{}'", key)),
+                        None => Ok((0, rows_spec)),
+                    }
+                }
+            },
+        );
+    print_summary(&spec, containers, materials, sheet);
+
+    let columns = if spec.column_widths.is_empty() {
+        spec.columns.max(1).to_string()
+    } else {
+        join(&spec.column_widths)
+    };
+    (spec.columns, spec.column_widths) = ask(
+        "Columns, number or container widths in mm like 170,250",
+        &columns,
+        |answer| {
+            if answer.contains(',') {
+                Ok((0, parse_list::<usize>(answer)?))
+            } else {
+                match answer.parse::<usize>() {
+                    Ok(0) | Err(_) => Err(format!("Invalid number of columns '{}'", answer)),
+                    Ok(columns) => Ok((columns, vec![])),
+                }
+            }
+        },
+    );
+    print_summary(&spec, containers, materials, sheet);
+
+    let material = if !spec.material.is_empty() {
+        spec.material.clone()
+    } else if spec.material_thickness > 0.0 {
+        spec.material_thickness.to_string()
+    } else {
+        "4".to_string()
+    };
+    (spec.material_thickness, spec.material) = ask(
+        "Material thickness in mm or preset name",
+        &material,
+        |answer| match answer.parse::<f32>() {
+            Ok(thickness) if thickness > 0.0 => Ok((thickness, String::new())),
+            Ok(_) => Err(format!("Invalid thickness '{}'", answer)),
+            Err(_) => materials
+                .iter()
+                .find(|material| material.name == answer)
+                .map(|material| (0.0, material.name.clone()))
+                .ok_or(format!("No material found for '{}'", answer)),
+        },
+    );
+    print_summary(&spec, containers, materials, sheet);
+
+    spec
+}
+
+/// Ask yes or no question, yes is the default
+pub fn confirm(question: &str) -> bool {
+    ask(question, "y", |answer| {
+        match answer.to_lowercase().as_str() {
+            "y" | "yes" => Ok(true),
+            "n" | "no" => Ok(false),
+            _ => Err("Answer y or n".to_string()),
+        }
+    })
+}
+
+/// Ask until the answer is valid, empty answer uses the default
+fn ask<T>(question: &str, default: &str, parse: impl Fn(&str) -> Result<T, String>) -> T {
+    loop {
+        eprint!("{} [{}]: ", question, default);
+        io::stderr().flush().unwrap();
+
+        let mut answer = String::new();
+        if io::stdin().read_line(&mut answer).unwrap_or_default() == 0 {
+            // stdin closed, nobody is there to answer
+            std::process::exit(1);
+        }
+        let answer = match answer.trim() {
+            "" => default,
+            answer => answer,
+        };
+
+        match parse(answer) {
+            Ok(value) => return value,
+            Err(error) => eprintln!("{}.", error),
+        }
+    }
+}
+
+fn print_summary(
+    spec: &RackSpec,
+    containers: &[Container],
+    materials: &[Material],
+    sheet: &SheetSize,
+) {
+    let mut spec = spec.clone();
+    if spec.material.is_empty() && spec.material_thickness <= 0.0 {
+        spec.material_thickness = 4.0;
+    }
+    if spec.rows == 0 && spec.rows_spec.is_empty() {
+        spec.rows = 1;
+    }
+    if spec.columns == 0 && spec.column_widths.is_empty() {
+        spec.columns = 1;
+    }
+
+    let layout = spec
+        .material(materials)
+        .and_then(|material| Ok((material, spec.layout(containers)?)));
+    match layout {
+        Ok((material, layout)) => {
+            let thickness = material.measured_thickness;
+            let (width, height, depth) = layout.outer_size(thickness);
+            let items = bill_of_materials(&layout, thickness);
+            let sheets = match sheet_count(&items, sheet) {
+                Ok(sheets) => sheets.to_string(),
+                Err(error) => error.to_string(),
+            };
+            eprintln!(
+                "  -> {} rows, {} columns in {}mm material: outer size {}x{}x{}mm, {} parts, sheets of {}mm: {}",
+                layout.rows.len(),
+                layout.columns(),
+                thickness,
+                width,
+                height,
+                depth,
+                items.iter().map(|item| item.quantity).sum::<usize>(),
+                sheet,
+                sheets
+            );
+        }
+        Err(error) => eprintln!("  -> {}.", error),
+    }
+}
+
+fn parse_list<T: FromStr>(value: &str) -> Result<Vec<T>, String> {
+    value
+        .split(',')
+        .map(|item| {
+            item.trim()
+                .parse::<T>()
+                .map_err(|_| format!("Invalid value '{}'", item.trim()))
+        })
+        .collect()
+}
+
+fn join<T: ToString>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
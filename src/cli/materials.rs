@@ -0,0 +1,130 @@
+use std::path::{Path, PathBuf};
+
+use comfy_table::{Cell, Table};
+use container_rack_lib::rack::material::{material_presets, merge_materials, Material};
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+struct MaterialsFile {
+    materials: Vec<Material>,
+}
+
+/// Built-in material presets and materials from the config file.
+///
+/// Without explicit file `$XDG_CONFIG_HOME/container-rack/materials.toml` (or
+/// `~/.config/container-rack/materials.toml`) is read when it exists.
+pub fn load_materials(file: Option<&Path>) -> Vec<Material> {
+    let path = match file {
+        Some(file) => file.to_path_buf(),
+        None => match default_materials_file() {
+            Some(path) if path.exists() => path,
+            _ => return material_presets(),
+        },
+    };
+
+    match read_materials(&path) {
+        Ok(user_materials) => merge_materials(material_presets(), user_materials),
+        Err(error) => {
+            println!("Can't read materials from {}: {}", path.display(), error);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Materials listed in `[[materials]]` tables, missing fields are 0 or empty
+fn read_materials(path: &Path) -> Result<Vec<Material>, String> {
+    let content = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+    toml::from_str::<MaterialsFile>(&content)
+        .map(|file| file.materials)
+        .map_err(|error| error.to_string())
+}
+
+fn default_materials_file() -> Option<PathBuf> {
+    let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+
+    Some(config_dir.join("container-rack").join("materials.toml"))
+}
+
+/// Print materials to CLI
+pub fn print_materials(materials: &[Material]) {
+    let mut table = Table::new();
+    table.set_header(vec![
+        Cell::new("Name"),
+        Cell::new("Description"),
+        Cell::new("Nominal (mm)"),
+        Cell::new("Measured (mm)"),
+        Cell::new("Kerf (mm)"),
+        Cell::new("Fit clearance (mm)"),
+        Cell::new("Price per m²"),
+    ]);
+
+    materials.iter().for_each(|material| {
+        table.add_row(vec![
+            Cell::new(&material.name),
+            Cell::new(&material.description),
+            Cell::new(material.nominal_thickness),
+            Cell::new(material.measured_thickness),
+            Cell::new(material.kerf),
+            Cell::new(material.fit_clearance),
+            if material.price_per_square_meter > 0.0 {
+                Cell::new(material.price_per_square_meter)
+            } else {
+                Cell::new("no price set")
+            },
+        ]);
+    });
+
+    println!("{table}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_materials_are_read_from_toml() {
+        let path = std::env::temp_dir().join("container-rack-test-materials.toml");
+        let content = [
+            "[[materials]]",
+            "name = \"birch-4mm\"",
+            "measured_thickness = 3.8",
+            "price_per_square_meter = 12.5",
+            "",
+            "[[materials]]",
+            "name = \"oak-5mm\"",
+            "nominal_thickness = 5",
+            "measured_thickness = 5.1",
+        ];
+        std::fs::write(&path, content.join("\n")).unwrap();
+
+        let materials = read_materials(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(materials.len(), 2);
+        assert_eq!(
+            materials[0],
+            Material {
+                name: "birch-4mm".to_string(),
+                measured_thickness: 3.8,
+                price_per_square_meter: 12.5,
+                ..Material::default()
+            }
+        );
+        assert_eq!(materials[1].nominal_thickness, 5.0);
+    }
+
+    #[test]
+    fn bad_materials_file_is_an_error() {
+        let path = std::env::temp_dir().join("container-rack-test-bad-materials.toml");
+        std::fs::write(&path, "[[materials]]\nmeasured_thickness = \"thick\"\n").unwrap();
+
+        let error = read_materials(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(error.is_err());
+        assert!(read_materials(Path::new("/nonexistent/materials.toml")).is_err());
+    }
+}
@@ -1,8 +1,21 @@
+use std::path::PathBuf;
+
 use clap::{Parser, Subcommand};
 
 pub mod generate;
 use generate::RackGenerationArgs;
+pub mod batch;
+use batch::BatchArgs;
+pub mod bom;
+pub mod calibrate;
+use calibrate::CalibrateArgs;
 pub mod containers;
+pub mod estimate;
+pub mod interactive;
+pub mod materials;
+use containers::ContainersArgs;
+#[cfg(feature = "serve")]
+pub mod serve;
 
 #[derive(Parser, Debug)]
 #[structopt(name = "Container rack")]
@@ -11,25 +24,52 @@ struct CommandLineCommands {
     /// Command to use: hours, integrations etc
     #[command(subcommand)]
     command: Commands,
+
+    /// TOML file with `[[materials]]` to add to the material presets
+    #[arg(long, global = true)]
+    materials_file: Option<PathBuf>,
 }
 
 #[derive(Subcommand, Debug)]
 enum Commands {
     /// Generate SVG
-    Generate(RackGenerationArgs),
+    Generate(Box<RackGenerationArgs>),
     /// List supported containers
-    Containers,
+    Containers(ContainersArgs),
+    /// List material presets
+    Materials,
+    /// Generate test-fit coupon with slots of different widths
+    Calibrate(CalibrateArgs),
+    /// Generate racks listed in a TOML or CSV file
+    Batch(BatchArgs),
+    /// Run local HTTP server with a form and JSON API for generating racks
+    #[cfg(feature = "serve")]
+    Serve(serve::ServeArgs),
 }
 
 pub fn run() {
     let args = CommandLineCommands::parse();
+    let materials = materials::load_materials(args.materials_file.as_deref());
 
     match &args.command {
         Commands::Generate(args) => {
-            generate::svg(args);
+            generate::svg(args, &materials);
+        }
+        Commands::Containers(args) => {
+            containers::print_containers(args);
+        }
+        Commands::Materials => {
+            materials::print_materials(&materials);
+        }
+        Commands::Calibrate(args) => {
+            calibrate::coupon(args, &materials);
+        }
+        Commands::Batch(args) => {
+            batch::generate(args, &materials);
         }
-        Commands::Containers => {
-            containers::print_containers();
+        #[cfg(feature = "serve")]
+        Commands::Serve(args) => {
+            serve::serve(args, &materials);
         }
     }
 }
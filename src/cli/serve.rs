@@ -0,0 +1,362 @@
+use clap::{Parser, ValueEnum};
+use container_rack_lib::rack::bom::bill_of_materials;
+use container_rack_lib::rack::material::Material;
+use container_rack_lib::rack::spec::RackSpec;
+use container_rack_lib::rack::Container;
+use container_rack_lib::{generate_layout_svg, render_dxf, render_svg, supported_containers};
+use std::io::{Cursor, Read};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use super::containers::details;
+
+#[derive(Parser, Debug)]
+pub struct ServeArgs {
+    /// Address to listen on, use 0.0.0.0 to allow other devices in the network
+    #[arg(long, default_value = "127.0.0.1")]
+    host: String,
+
+    /// Port to listen on
+    #[arg(short, long, default_value_t = 8080)]
+    port: u16,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+enum OutputFormat {
+    Svg,
+    Dxf,
+    Bom,
+}
+
+/// Largest accepted body of a POST request, rack spec JSON is far smaller
+const MAX_BODY_LENGTH: usize = 64 * 1024;
+
+/// File generated for the client
+struct Output {
+    content_type: &'static str,
+    /// Name of the downloaded file, `None` shows content in the browser
+    filename: Option<String>,
+    body: String,
+}
+
+/// Run HTTP server with a form and JSON API for generating racks
+pub fn serve(args: &ServeArgs, materials: &[Material]) {
+    let server = match Server::http((args.host.as_str(), args.port)) {
+        Ok(server) => server,
+        Err(error) => {
+            println!("Can't listen on {}:{}: {}", args.host, args.port, error);
+            std::process::exit(1);
+        }
+    };
+    println!("Listening on http://{}:{}", args.host, args.port);
+
+    for mut request in server.incoming_requests() {
+        let response = match handle(&mut request, materials).and_then(response) {
+            Ok(response) => response,
+            Err((status, message)) => {
+                let response = Response::from_string(message).with_status_code(status);
+                match header("Content-Type", "text/plain; charset=utf-8") {
+                    Ok(content_type) => response.with_header(content_type),
+                    Err(_) => response,
+                }
+            }
+        };
+
+        if let Err(error) = request.respond(response) {
+            println!("Can't send response: {}", error);
+        }
+    }
+}
+
+fn response(output: Output) -> Result<Response<Cursor<Vec<u8>>>, (u16, String)> {
+    let mut response = Response::from_string(output.body)
+        .with_header(header("Content-Type", output.content_type)?);
+    if let Some(filename) = output.filename {
+        response = response.with_header(header(
+            "Content-Disposition",
+            &format!("attachment; filename=\"{}\"", filename),
+        )?);
+    }
+    Ok(response)
+}
+
+fn handle(request: &mut Request, materials: &[Material]) -> Result<Output, (u16, String)> {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let containers = supported_containers();
+
+    match (request.method(), path) {
+        (Method::Get, "/") => Ok(Output {
+            content_type: "text/html; charset=utf-8",
+            filename: None,
+            body: form_html(&containers, materials),
+        }),
+        (Method::Get, "/api/containers") => Ok(Output {
+            content_type: "application/json",
+            filename: None,
+            body: serde_json::to_string(&details(&containers.iter().collect::<Vec<_>>())).unwrap(),
+        }),
+        (Method::Get, "/api/materials") => Ok(Output {
+            content_type: "application/json",
+            filename: None,
+            body: serde_json::to_string(materials).unwrap(),
+        }),
+        (Method::Get, "/rack") => {
+            let spec = spec_from_query(query).map_err(bad_request)?;
+            generate(&spec, format_from_query(query)?, &containers, materials)
+        }
+        (Method::Post, "/api/rack") => {
+            let length = request.body_length();
+            let body = read_body(request.as_reader(), length)?;
+            let spec: RackSpec = serde_json::from_str(&body).map_err(bad_request)?;
+            generate(&spec, format_from_query(query)?, &containers, materials)
+        }
+        _ => Err((404, "Not found".to_string())),
+    }
+}
+
+fn generate(
+    spec: &RackSpec,
+    format: OutputFormat,
+    containers: &[Container],
+    materials: &[Material],
+) -> Result<Output, (u16, String)> {
+    let material = spec.material(materials).map_err(bad_request)?;
+    let layout = spec.layout(containers).map_err(bad_request)?;
+    let filename = spec.default_filename();
+
+    Ok(match format {
+        OutputFormat::Bom => Output {
+            content_type: "application/json",
+            filename: None,
+            body: serde_json::to_string(&bill_of_materials(&layout, material.measured_thickness))
+                .unwrap(),
+        },
+        OutputFormat::Svg | OutputFormat::Dxf => {
+            let svg = generate_layout_svg(
+                &layout,
+                &material,
+                &spec.primary_color,
+                &spec.secondary_color,
+            )
+            .map_err(bad_request)?;
+            if format == OutputFormat::Svg {
+                Output {
+                    content_type: "image/svg+xml",
+                    filename: Some(format!("{}.svg", filename)),
+                    body: render_svg(&svg),
+                }
+            } else {
+                Output {
+                    content_type: "application/dxf",
+                    filename: Some(format!("{}.dxf", filename)),
+                    body: render_dxf(&svg),
+                }
+            }
+        }
+    })
+}
+
+fn format_from_query(query: &str) -> Result<OutputFormat, (u16, String)> {
+    match query_pairs(query).find(|(key, _)| key == "format") {
+        Some((_, format)) => OutputFormat::from_str(&format, true).map_err(bad_request),
+        None => Ok(OutputFormat::Svg),
+    }
+}
+
+/// Read rack spec from form fields, same names as in `RackSpec`
+fn spec_from_query(query: &str) -> Result<RackSpec, String> {
+    let mut spec = RackSpec::default();
+
+    for (field, value) in query_pairs(query) {
+        if !value.trim().is_empty() && field != "format" {
+            spec.set(&field, &value)?;
+        }
+    }
+
+    Ok(spec)
+}
+
+fn query_pairs(query: &str) -> impl Iterator<Item = (String, String)> + '_ {
+    url::form_urlencoded::parse(query.as_bytes()).into_owned()
+}
+
+fn bad_request(error: impl ToString) -> (u16, String) {
+    (400, error.to_string())
+}
+
+/// Read request body up to `MAX_BODY_LENGTH`, longer body is rejected with 413
+fn read_body(reader: impl Read, length: Option<usize>) -> Result<String, (u16, String)> {
+    let too_large = || {
+        (
+            413,
+            format!("Request body is over {} bytes", MAX_BODY_LENGTH),
+        )
+    };
+    if length.is_some_and(|length| length > MAX_BODY_LENGTH) {
+        return Err(too_large());
+    }
+
+    let mut body = String::new();
+    reader
+        .take(MAX_BODY_LENGTH as u64 + 1)
+        .read_to_string(&mut body)
+        .map_err(bad_request)?;
+    if body.len() > MAX_BODY_LENGTH {
+        return Err(too_large());
+    }
+    Ok(body)
+}
+
+fn header(name: &str, value: &str) -> Result<Header, (u16, String)> {
+    Header::from_bytes(name.as_bytes(), value.as_bytes())
+        .map_err(|_| bad_request(format!("Can't put \"{}\" in {} header", value, name)))
+}
+
+/// Escape text for HTML content and attribute values
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn form_html(containers: &[Container], materials: &[Material]) -> String {
+    let options = containers
+        .iter()
+        .map(|container| {
+            format!(
+                "<option value=\"{}\">{} {}</option>",
+                escape_html(&container.key()),
+                escape_html(&container.vendor),
+                escape_html(&container.model)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("");
+
+    let material_options = materials
+        .iter()
+        .map(|material| {
+            format!(
+                "<option value=\"{}\">{}</option>",
+                escape_html(&material.name),
+                escape_html(&material.to_string())
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("");
+
+    FORM_HTML
+        .replace("{options}", &options)
+        .replace("{material_options}", &material_options)
+}
+
+const FORM_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Container rack</title>
+<style>
+body { font-family: sans-serif; max-width: 30em; margin: 1em auto; padding: 0 1em; }
+label { display: block; margin-top: 0.8em; }
+input, select, button { width: 100%; font-size: 1.2em; }
+small { color: #555; }
+</style>
+</head>
+<body>
+<h1>Container rack</h1>
+<form action="/rack" method="get">
+<label>Container <select name="container">{options}</select></label>
+<label>Rows <input name="rows" type="number" min="1" value="4"></label>
+<label>Rows spec <input name="rows_spec" placeholder="1,1,2,3">
+<small>Overrides rows. Multiples of container height, heights like 120mm or container keys</small></label>
+<label>Columns <input name="columns" type="number" min="1" value="2"></label>
+<label>Column widths <input name="column_widths" placeholder="170,250">
+<small>Overrides columns. Container widths in mm</small></label>
+<label>Material <select name="material"><option value="">Thickness below</option>{material_options}</select></label>
+<label>Material thickness (mm) <input name="material_thickness" type="number" step="0.01" min="0.1" value="4">
+<small>Used when no material is selected</small></label>
+<label><input name="common_line" type="checkbox" value="true" style="width: auto"> Common line
+<small>Neighbouring side wings share one cut line</small></label>
+<label>Corner joint <select name="corner_joint">
+<option value="taps">Taps</option>
+<option value="fingers">Fingers</option>
+<option value="snap-fit">Snap-fit</option>
+<option value="t-slot">T-slot screws</option>
+<option value="dovetail">Dovetail</option>
+</select></label>
+<label>Screw <select name="screw">
+<option value="m3" selected>M3</option>
+<option value="m4">M4</option>
+<option value="m5">M5</option>
+</select>
+<small>Used by the T-slot joint</small></label>
+<label>Wall mount <select name="wall_mount">
+<option value="">None</option>
+<option value="back-stretcher">Back stretcher</option>
+<option value="side-panels">Side panels</option>
+</select></label>
+<label><input name="stacking" type="checkbox" value="true" style="width: auto"> Stacking
+<small>Holes and pegs for stacking racks with the same columns</small></label>
+<label>Drawer stop <select name="drawer_stop">
+<option value="">None</option>
+<option value="tab">Tab</option>
+<option value="notch">Notch</option>
+</select></label>
+<label>Drawer stop depth (mm) <input name="drawer_stop_depth" type="number" step="0.5" min="0.5" value="3"></label>
+<label>Retention bars <select name="retention_bars">
+<option value="">None</option>
+<option value="rows">Rows</option>
+<option value="columns">Columns</option>
+</select>
+<small>Removable bars in front of the containers, e.g. for vans</small></label>
+<label>Format <select name="format">
+<option value="svg">SVG</option>
+<option value="dxf">DXF</option>
+<option value="bom">Bill of materials (JSON)</option>
+</select></label>
+<p><button type="submit">Generate</button></p>
+</form>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn form_escapes_names() {
+        let material = Material {
+            name: "<script>\"x\"</script>".to_string(),
+            ..Material::default()
+        };
+        let html = form_html(&supported_containers(), &[material]);
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("<option value=\"&lt;script&gt;&quot;x&quot;&lt;/script&gt;\">"));
+    }
+
+    #[test]
+    fn body_over_the_limit_is_rejected() {
+        let body = "{}".repeat(MAX_BODY_LENGTH);
+        assert_eq!(read_body(body.as_bytes(), None).unwrap_err().0, 413);
+        assert_eq!(
+            read_body("{}".as_bytes(), Some(body.len())).unwrap_err().0,
+            413
+        );
+        assert_eq!(read_body("{}".as_bytes(), Some(2)), Ok("{}".to_string()));
+    }
+
+    #[test]
+    fn invalid_header_is_an_error() {
+        assert!(header("Content-Disposition", "attachment; filename=\"rack.svg\"").is_ok());
+        assert_eq!(
+            header("Content-Disposition", "rack\u{e9}.svg")
+                .unwrap_err()
+                .0,
+            400
+        );
+    }
+}
@@ -1,36 +1,191 @@
 pub mod rack;
-use std::vec;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 use url::Url;
 
 use rack::{Container, ContainerLink, Dimensions};
 
-pub use crate::rack::generate_svg;
+pub use crate::rack::dxf::render_dxf;
+pub use crate::rack::gcode::render_gcode;
+pub use crate::rack::{
+    generate_layout_svg, generate_svg, generate_svg_with_rows, render_svg, write_svg,
+};
 
+const SMARTSTORE_CLASSIC_GROUP: &str = "smartstore-classic-small";
+const IKEA_SAMLA_39X28_GROUP: &str = "ikea-samla-39x28";
+const REALLY_USEFUL_BOX_395X255_GROUP: &str = "really-useful-box-395x255";
+
+/// Containers in the same compatibility group share the footprint and can be mixed in rows
 pub fn supported_containers() -> Vec<Container> {
-    vec![Container {
+    vec![
+        smartstore(
+            "Classic 1",
+            "Rack generated with this container can also have classic 2 and classic 3 containers. And 'Home' versions of same containers.",
+            "https://www.orthexgroup.com/smartstore-classic/909-smartstore-classic-1-7310543520075.html",
+            1,
+        ),
+        smartstore(
+            "Classic 2",
+            "Twice as tall as Classic 1, takes two Classic 1 rows.",
+            "https://www.orthexgroup.com/smartstore-classic/918-smartstore-classic-2-7310543521072.html",
+            2,
+        ),
+        smartstore(
+            "Classic 3",
+            "Three times as tall as Classic 1, takes three Classic 1 rows.",
+            "https://www.orthexgroup.com/smartstore-classic/919-smartstore-classic-3-7310543522079.html",
+            3,
+        ),
+        smartstore(
+            "Home 1",
+            "Same body as Classic 1.",
+            "https://www.orthexgroup.com/search?controller=search&s=smartstore+home+1",
+            1,
+        ),
+        smartstore(
+            "Home 2",
+            "Same body as Classic 2.",
+            "https://www.orthexgroup.com/search?controller=search&s=smartstore+home+2",
+            2,
+        ),
+        smartstore(
+            "Home 3",
+            "Same body as Classic 3.",
+            "https://www.orthexgroup.com/search?controller=search&s=smartstore+home+3",
+            3,
+        ),
+        Container {
+            vendor: "IKEA".to_string(),
+            model: "SAMLA 11 l".to_string(),
+            description: "Nominal dimensions from the manufacturer, measure your box before cutting. Can share a rack with SAMLA 22 l.".to_string(),
+            compatibility_group: IKEA_SAMLA_39X28_GROUP.to_string(),
+            links: vec![link("https://www.ikea.com/search/?q=samla%2011", "IKEA SAMLA 11 l")],
+            dimensions: Dimensions {
+                width: 280,
+                depth: 390,
+                height: 140,
+                side_wing_from_box_top: 15,
+                side_wing_width: 8,
+            },
+        },
+        Container {
+            vendor: "IKEA".to_string(),
+            model: "SAMLA 22 l".to_string(),
+            description: "Nominal dimensions from the manufacturer, measure your box before cutting. Can share a rack with SAMLA 11 l.".to_string(),
+            compatibility_group: IKEA_SAMLA_39X28_GROUP.to_string(),
+            links: vec![link("https://www.ikea.com/search/?q=samla%2022", "IKEA SAMLA 22 l")],
+            dimensions: Dimensions {
+                width: 280,
+                depth: 390,
+                height: 280,
+                side_wing_from_box_top: 15,
+                side_wing_width: 8,
+            },
+        },
+        Container {
+            vendor: "Really Useful".to_string(),
+            model: "Box 4 l".to_string(),
+            description: "Nominal dimensions from the manufacturer, measure your box before cutting. Can share a rack with Box 9 l.".to_string(),
+            compatibility_group: REALLY_USEFUL_BOX_395X255_GROUP.to_string(),
+            links: vec![link(
+                "https://www.reallyusefulproducts.co.uk/search?q=4+litre+box",
+                "Really Useful Box 4 l",
+            )],
+            dimensions: Dimensions {
+                width: 255,
+                depth: 395,
+                height: 88,
+                side_wing_from_box_top: 12,
+                side_wing_width: 8,
+            },
+        },
+        Container {
+            vendor: "Really Useful".to_string(),
+            model: "Box 9 l".to_string(),
+            description: "Nominal dimensions from the manufacturer, measure your box before cutting. Can share a rack with Box 4 l.".to_string(),
+            compatibility_group: REALLY_USEFUL_BOX_395X255_GROUP.to_string(),
+            links: vec![link(
+                "https://www.reallyusefulproducts.co.uk/search?q=9+litre+box",
+                "Really Useful Box 9 l",
+            )],
+            dimensions: Dimensions {
+                width: 255,
+                depth: 395,
+                height: 155,
+                side_wing_from_box_top: 12,
+                side_wing_width: 8,
+            },
+        },
+    ]
+}
+
+/// SmartStore containers share footprint with Classic 1, taller models take multiple rows
+fn smartstore(model: &str, description: &str, url: &str, rows: usize) -> Container {
+    Container {
         vendor: "SmartStore".to_string(),
-        model: "Classic 1".to_string(),
-        description: "Rack generated with this container can also have classic 2 and classic 3 containers. And 'Home' versions of same containers.".to_string(),
-        links: vec![
-          ContainerLink{
-            url: Url::parse("https://www.orthexgroup.com/smartstore-classic/909-smartstore-classic-1-7310543520075.html").unwrap(),
-            title: "SmartStore Classic 1".to_string(),
-          },
-          ContainerLink{
-            url: Url::parse("https://www.orthexgroup.com/smartstore-classic/918-smartstore-classic-2-7310543521072.html").unwrap(),
-            title: "SmartStore Classic 2".to_string(),
-          },
-          ContainerLink{
-            url: Url::parse("https://www.orthexgroup.com/smartstore-classic/919-smartstore-classic-3-7310543522079.html").unwrap(),
-            title: "SmartStore Classic 3".to_string(),
-          }
-          ],
-          dimensions: Dimensions {
+        model: model.to_string(),
+        description: description.to_string(),
+        compatibility_group: SMARTSTORE_CLASSIC_GROUP.to_string(),
+        links: vec![link(url, &format!("SmartStore {}", model))],
+        dimensions: Dimensions {
             width: 170,
             depth: 210,
-            height: 56,
+            height: 56 * rows,
             side_wing_from_box_top: 15,
             side_wing_width: 8,
-          }
-    }]
+        },
+    }
+}
+
+fn link(url: &str, title: &str) -> ContainerLink {
+    ContainerLink {
+        url: Url::parse(url).unwrap(),
+        title: title.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn containers_have_unique_keys_and_own_links() {
+        let containers = supported_containers();
+        let keys: HashSet<String> = containers.iter().map(Container::key).collect();
+        assert_eq!(keys.len(), containers.len());
+        assert!(keys.contains("smartstore-home_1"));
+        assert!(keys.contains("ikea-samla_22_l"));
+        assert!(keys.contains("really_useful-box_9_l"));
+
+        for container in &containers {
+            assert!(!container.links.is_empty(), "{}", container.key());
+            assert!(
+                container
+                    .links
+                    .iter()
+                    .all(|link| link.title.ends_with(&container.model)),
+                "{}",
+                container.key()
+            );
+        }
+    }
+
+    #[test]
+    fn containers_of_a_group_share_the_footprint() {
+        let containers = supported_containers();
+        for container in &containers {
+            for other in containers
+                .iter()
+                .filter(|other| other.compatibility_group == container.compatibility_group)
+            {
+                assert_eq!(container.check_compatible(other), Ok(()));
+            }
+        }
+        let classic_1 = &containers[0];
+        assert!(containers
+            .iter()
+            .filter(|other| other.compatibility_group != classic_1.compatibility_group)
+            .all(|other| classic_1.check_compatible(other).is_err()));
+    }
 }
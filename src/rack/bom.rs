@@ -0,0 +1,240 @@
+use super::label::{LABEL_HOLDER_HEIGHT, LABEL_HOLDER_WIDTH};
+use super::mount::WallMount;
+use super::retention::bar_sizes;
+use super::stop::{self, DrawerStop};
+use super::{
+    front_overhang, rows_height, stacking_peg_positions, top_width, Layout, BACK_STRETCHER_HEIGHT,
+    STACKING_PEG_WIDTH,
+};
+
+/// Single line in the bill of materials
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BomItem {
+    pub part: String,
+    pub quantity: usize,
+    /// Size of the part along the depth of the rack in mm
+    pub length: f32,
+    /// Size of the part across the depth of the rack in mm
+    pub width: f32,
+}
+
+/// List parts needed for the rack, identical parts are grouped together.
+pub fn bill_of_materials(layout: &Layout, material_thickness: f32) -> Vec<BomItem> {
+    let columns = layout.columns();
+    let mut items: Vec<BomItem> = vec![];
+
+    for row in &layout.rows {
+        let item = BomItem {
+            part: "Side wing".to_string(),
+            quantity: 2 * columns,
+            length: row.depth as f32,
+            // Wing with its taps
+            width: row.side_wing_width as f32 + material_thickness,
+        };
+        add_item(&mut items, item);
+    }
+
+    add_item(
+        &mut items,
+        BomItem {
+            part: "Top and bottom cover".to_string(),
+            quantity: 2,
+            length: layout.depth() as f32,
+            width: top_width(
+                &layout.column_pitches(material_thickness),
+                material_thickness,
+            ),
+        },
+    );
+
+    add_item(
+        &mut items,
+        BomItem {
+            part: "Side panel".to_string(),
+            quantity: columns + 1,
+            // Tabs for the label holders stick out of the front
+            length: layout.depth() as f32
+                + front_overhang(&layout.slot_labels(), material_thickness),
+            width: rows_height(&layout.rows) as f32 + 2.0 * material_thickness,
+        },
+    );
+
+    if layout.options.wall_mount == Some(WallMount::BackStretcher) {
+        add_item(
+            &mut items,
+            BomItem {
+                part: "Back stretcher".to_string(),
+                quantity: 1,
+                // Stretcher stands up, its height with the tabs is listed as the length
+                length: BACK_STRETCHER_HEIGHT as f32 + material_thickness,
+                width: top_width(
+                    &layout.column_pitches(material_thickness),
+                    material_thickness,
+                ),
+            },
+        );
+    }
+
+    if layout.options.stacking {
+        add_item(
+            &mut items,
+            BomItem {
+                part: "Stacking peg".to_string(),
+                quantity: stacking_peg_positions(
+                    layout.depth(),
+                    &layout.column_pitches(material_thickness),
+                    material_thickness,
+                )
+                .len(),
+                length: STACKING_PEG_WIDTH as f32,
+                width: 2.0 * material_thickness,
+            },
+        );
+    }
+    if let Some(bars) = layout.options.retention_bars {
+        let sizes = bar_sizes(
+            bars,
+            &layout.rows,
+            &layout.column_pitches(material_thickness),
+            material_thickness,
+        );
+        for (width, height) in sizes {
+            add_item(
+                &mut items,
+                BomItem {
+                    part: "Retention bar".to_string(),
+                    quantity: 1,
+                    length: height,
+                    width,
+                },
+            );
+        }
+    }
+    if layout.options.drawer_stop == Some(DrawerStop::Tab) {
+        let (length, width) =
+            stop::piece_size(layout.options.drawer_stop_depth, material_thickness);
+        add_item(
+            &mut items,
+            BomItem {
+                part: "Drawer stop".to_string(),
+                quantity: 2 * columns * layout.rows.len(),
+                length,
+                width,
+            },
+        );
+    }
+    let labels = layout.slot_labels();
+    if !labels.is_empty() {
+        add_item(
+            &mut items,
+            BomItem {
+                part: "Label holder".to_string(),
+                quantity: labels.len(),
+                length: LABEL_HOLDER_HEIGHT as f32,
+                width: LABEL_HOLDER_WIDTH as f32,
+            },
+        );
+    }
+
+    items
+}
+
+/// Combine bills of materials, e.g. of several racks cut from the same material
+pub fn combine<'a>(lists: impl IntoIterator<Item = &'a [BomItem]>) -> Vec<BomItem> {
+    let mut items = vec![];
+    lists
+        .into_iter()
+        .flatten()
+        .for_each(|item| add_item(&mut items, item.clone()));
+    items
+}
+
+/// Sizes are rounded to 0.1mm, so float noise doesn't split groups or show up in the list
+fn add_item(items: &mut Vec<BomItem>, item: BomItem) {
+    let item = BomItem {
+        length: round_mm(item.length),
+        width: round_mm(item.width),
+        ..item
+    };
+    match items.iter_mut().find(|existing| {
+        existing.part == item.part && existing.length == item.length && existing.width == item.width
+    }) {
+        Some(existing) => existing.quantity += item.quantity,
+        None => items.push(item),
+    }
+}
+
+fn round_mm(value: f32) -> f32 {
+    (value * 10.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rack::Dimensions;
+
+    /// SmartStore Classic 1
+    fn classic_1() -> Dimensions {
+        Dimensions {
+            width: 170,
+            depth: 210,
+            height: 56,
+            side_wing_from_box_top: 15,
+            side_wing_width: 8,
+        }
+    }
+
+    #[test]
+    fn sizes_are_rounded_to_tenth_of_mm() {
+        let items = bill_of_materials(&Layout::uniform(&classic_1(), 2, 2), 3.8);
+        let cover = items
+            .iter()
+            .find(|item| item.part == "Top and bottom cover")
+            .unwrap();
+        assert_eq!(cover.width.to_string(), "363.2");
+        assert!(
+            items
+                .iter()
+                .all(|item| item.width == round_mm(item.width)
+                    && item.length == round_mm(item.length))
+        );
+    }
+
+    #[test]
+    fn identical_parts_are_grouped() {
+        let items = bill_of_materials(&Layout::uniform(&classic_1(), 3, 2), 4.0);
+        let wings = items.iter().find(|item| item.part == "Side wing").unwrap();
+        assert_eq!(wings.quantity, 12);
+    }
+
+    #[test]
+    fn tab_stops_are_listed_without_widening_the_wings() {
+        let mut layout = Layout::uniform(&classic_1(), 3, 2);
+        layout.options.drawer_stop = Some(DrawerStop::Tab);
+        let items = bill_of_materials(&layout, 4.0);
+        let wings = items.iter().find(|item| item.part == "Side wing").unwrap();
+        assert_eq!(wings.width, 12.0);
+        let stops = items
+            .iter()
+            .find(|item| item.part == "Drawer stop")
+            .unwrap();
+        assert_eq!((stops.quantity, stops.length, stops.width), (12, 10.0, 7.0));
+    }
+
+    #[test]
+    fn side_panels_are_longer_by_the_label_tabs() {
+        let mut layout = Layout::uniform(&classic_1(), 2, 2);
+        let panel_length = |layout: &Layout| {
+            bill_of_materials(layout, 4.0)
+                .into_iter()
+                .find(|item| item.part == "Side panel")
+                .unwrap()
+                .length
+        };
+        assert_eq!(panel_length(&layout), 210.0);
+
+        layout.labels.insert((0, 1), "Screws".to_string());
+        assert_eq!(panel_length(&layout), 214.0);
+    }
+}
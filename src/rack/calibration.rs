@@ -0,0 +1,178 @@
+//! Test-fit coupon: one side wing and a strip of side panel slots of different widths.
+//!
+//! Cut it before the rack and push the wing tab into each slot, the label of the slot
+//! that fits best is the fit clearance for the material.
+
+use svg::node::element::path::Data;
+use svg::node::element::{Path, Text};
+use svg::{Document, Node};
+
+use super::joint::{JointStyle, WingTabs};
+use super::material::Material;
+use super::{
+    generate_side_panel_wing_hole, generate_side_wing_path, geometry, CLEARANCE_BETWEEN_PATHS,
+    SIDE_WING_SLOT_WIDTH,
+};
+
+/// Shortest wing where the two tabs don't overlap
+const COUPON_WING_DEPTH: usize = 130;
+const COUPON_WING_WIDTH: usize = 15;
+const COUPON_MARGIN: f32 = 8.0;
+const COUPON_SLOT_SPACING: f32 = 8.0;
+const COUPON_LABEL_SIZE: f32 = 4.0;
+
+/// Clearances from `from` to `to` (both included) in `step` increments, rounded to 0.001mm
+pub fn clearance_steps(from: f32, to: f32, step: f32) -> Vec<f32> {
+    if step <= 0.0 || to < from {
+        return vec![from];
+    }
+    let count = ((to - from) / step).round() as usize;
+
+    (0..=count)
+        .map(|index| ((from + index as f32 * step) * 1000.0).round() / 1000.0)
+        .collect()
+}
+
+/// Generate coupon with a side wing and a side panel strip with a slot for every clearance.
+///
+/// Slot is measured thickness of the material plus the clearance, labels engraved in
+/// `engrave_color` show the clearance. Clearances that would make the slot zero or negative
+/// wide are left out.
+pub fn generate_calibration_svg(
+    material: impl Into<Material>,
+    clearances: &[f32],
+    primary_color: &str,
+    secondary_color: &str,
+    engrave_color: &str,
+) -> Document {
+    let material = material.into();
+    let material_thickness = material.measured_thickness;
+    let clearances = clearances
+        .iter()
+        .cloned()
+        .filter(|clearance| material_thickness + clearance > 0.0)
+        .collect::<Vec<f32>>();
+
+    let panel_y = (COUPON_WING_WIDTH + CLEARANCE_BETWEEN_PATHS) as f32 + material_thickness;
+    let slots_width = clearances
+        .iter()
+        .map(|clearance| material_thickness + clearance + COUPON_SLOT_SPACING)
+        .sum::<f32>()
+        - COUPON_SLOT_SPACING;
+    let panel_width = 2.0 * COUPON_MARGIN + slots_width.max(0.0);
+    let panel_height = 2.0 * COUPON_MARGIN + SIDE_WING_SLOT_WIDTH as f32 + 3.0 * COUPON_LABEL_SIZE;
+
+    let total_width = panel_width.max(COUPON_WING_DEPTH as f32);
+    let total_height = panel_y + panel_height;
+
+    let mut document = Document::new()
+        .set("viewBox", (0, 0, total_width, total_height))
+        .set("width", format!("{}mm", total_width))
+        .set("height", format!("{}mm", total_height));
+
+    document.append(
+        Path::new()
+            .set("fill", "none")
+            .set("stroke", secondary_color)
+            .set(
+                "d",
+                generate_side_wing_path(
+                    0.0,
+                    0.0,
+                    material_thickness,
+                    COUPON_WING_DEPTH,
+                    COUPON_WING_WIDTH,
+                    &WingTabs::default(),
+                    None,
+                ),
+            ),
+    );
+
+    let mut labels = vec![];
+    let mut x = COUPON_MARGIN;
+    let slot_y = panel_y + COUPON_MARGIN;
+    for clearance in &clearances {
+        let slot_width = material_thickness + clearance;
+        // First slot of the wing joint, moved to the top of the strip
+        let slot = &WingTabs::default().slots(COUPON_WING_DEPTH as f32, slot_width)[0];
+        document.append(generate_side_panel_wing_hole(
+            x,
+            slot_y - slot[0].0,
+            slot,
+            primary_color,
+        ));
+        labels.push(label(
+            x + slot_width / 2.0,
+            slot_y + SIDE_WING_SLOT_WIDTH as f32 + 1.5 * COUPON_LABEL_SIZE,
+            &format!("{:+.2}", clearance),
+            engrave_color,
+        ));
+        x += slot_width + COUPON_SLOT_SPACING;
+    }
+    labels.push(label(
+        panel_width / 2.0,
+        slot_y + SIDE_WING_SLOT_WIDTH as f32 + 3.0 * COUPON_LABEL_SIZE,
+        &format!("{}, slot = {}mm + label", material.name, material_thickness),
+        engrave_color,
+    ));
+
+    document.append(
+        Path::new()
+            .set("fill", "none")
+            .set("stroke", secondary_color)
+            .set(
+                "d",
+                Data::new()
+                    .move_to((0.0, panel_y))
+                    .vertical_line_to(panel_y + panel_height)
+                    .horizontal_line_to(panel_width)
+                    .vertical_line_to(panel_y)
+                    .close(),
+            ),
+    );
+
+    if material.kerf > 0.0 {
+        document = geometry::compensate_kerf(&document, material.kerf);
+    }
+    // Labels are added after kerf compensation, it keeps only paths
+    for text in labels {
+        document.append(text);
+    }
+
+    document
+}
+
+/// Engraved text centred on the point
+fn label(x: f32, y: f32, text: &str, color: &str) -> Text {
+    Text::new(text)
+        .set("x", x)
+        .set("y", y)
+        .set("fill", color)
+        .set("font-family", "sans-serif")
+        .set("font-size", COUPON_LABEL_SIZE)
+        .set("text-anchor", "middle")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_are_engraved_and_slots_cut() {
+        let document = generate_calibration_svg(4.0, &[-0.1, 0.0, 0.1], "black", "blue", "red");
+        let svg = document.to_string();
+
+        assert_eq!(svg.matches("<text").count(), 4);
+        assert_eq!(svg.matches("fill=\"red\"").count(), 4);
+        assert!(!svg.contains("fill=\"black\""));
+    }
+
+    #[test]
+    fn clearance_steps_include_both_ends() {
+        assert_eq!(
+            clearance_steps(-0.1, 0.1, 0.05),
+            vec![-0.1, -0.05, 0.0, 0.05, 0.1]
+        );
+        assert_eq!(clearance_steps(0.1, -0.1, 0.05), vec![0.1]);
+    }
+}
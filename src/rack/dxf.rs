@@ -0,0 +1,95 @@
+//! DXF output for CAD and laser software that doesn't read SVG.
+
+use std::fmt::Write;
+
+use svg::Document;
+
+use super::geometry::{document_contours, document_size};
+
+/// Render paths of the SVG document as DXF (R12) polylines.
+///
+/// Every stroke color gets its own layer. Y axis is flipped, as DXF grows upwards.
+pub fn render_dxf(document: &Document) -> String {
+    let (_, height) = document_size(document);
+    let mut dxf = String::new();
+
+    group(&mut dxf, 0, "SECTION");
+    group(&mut dxf, 2, "HEADER");
+    group(&mut dxf, 9, "$INSUNITS");
+    // Millimeters
+    group(&mut dxf, 70, "4");
+    group(&mut dxf, 0, "ENDSEC");
+
+    group(&mut dxf, 0, "SECTION");
+    group(&mut dxf, 2, "ENTITIES");
+    for contour in document_contours(document) {
+        group(&mut dxf, 0, "POLYLINE");
+        group(&mut dxf, 8, &contour.color);
+        group(&mut dxf, 66, "1");
+        group(&mut dxf, 70, if contour.closed { "1" } else { "0" });
+        for (x, y) in contour.points {
+            group(&mut dxf, 0, "VERTEX");
+            group(&mut dxf, 8, &contour.color);
+            group(&mut dxf, 10, &x.to_string());
+            group(&mut dxf, 20, &(height - y).to_string());
+        }
+        group(&mut dxf, 0, "SEQEND");
+        group(&mut dxf, 8, &contour.color);
+    }
+    group(&mut dxf, 0, "ENDSEC");
+    group(&mut dxf, 0, "EOF");
+
+    dxf
+}
+
+fn group(dxf: &mut String, code: u16, value: &str) {
+    writeln!(dxf, "{}\n{}", code, value).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rack::geometry::{contour_path, Contour};
+
+    fn document() -> Document {
+        let square = Contour {
+            points: vec![(10.0, 10.0), (30.0, 10.0), (30.0, 30.0), (10.0, 30.0)],
+            closed: true,
+            color: "black".to_string(),
+        };
+        let line = Contour {
+            points: vec![(0.0, 90.0), (50.0, 90.0)],
+            closed: false,
+            color: "red".to_string(),
+        };
+        Document::new()
+            .set("viewBox", (0, 0, 200, 100))
+            .add(contour_path(&square))
+            .add(contour_path(&line))
+    }
+
+    #[test]
+    fn contours_are_polylines_on_layer_of_their_color() {
+        let dxf = render_dxf(&document());
+        let lines = dxf.lines().collect::<Vec<&str>>();
+
+        let polylines = lines
+            .windows(8)
+            .filter(|window| window[1] == "POLYLINE")
+            .map(|window| (window[3], window[7]))
+            .collect::<Vec<_>>();
+        assert_eq!(polylines, vec![("black", "1"), ("red", "0")]);
+        assert_eq!(lines.iter().filter(|line| **line == "VERTEX").count(), 6);
+        assert!(dxf.starts_with("0\nSECTION\n2\nHEADER\n9\n$INSUNITS\n70\n4\n"));
+        assert!(dxf.ends_with("0\nENDSEC\n0\nEOF\n"));
+    }
+
+    #[test]
+    fn y_axis_grows_upwards() {
+        let dxf = render_dxf(&document());
+
+        assert!(dxf.contains("10\n10\n20\n90\n"));
+        assert!(dxf.contains("10\n30\n20\n70\n"));
+        assert!(dxf.contains("10\n50\n20\n10\n"));
+    }
+}
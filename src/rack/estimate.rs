@@ -0,0 +1,229 @@
+//! Cost and cutting time of a generated rack, for quoting before cutting.
+
+use std::collections::BTreeMap;
+
+use svg::Document;
+
+use super::geometry::{document_contours, document_parts};
+use super::nesting::{pack, PartTooLarge, SheetSize};
+use super::CLEARANCE_BETWEEN_PATHS;
+
+/// Speeds of the laser cutter
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct LaserProfile {
+    /// Cutting speed in mm/s
+    pub cut_speed: f32,
+    /// Cutting speed in mm/s per layer color, layers not listed use `cut_speed`
+    pub layer_speeds: BTreeMap<String, f32>,
+    /// Seconds the laser needs to pierce the material at the start of every contour
+    pub pierce_time: f32,
+}
+
+impl Default for LaserProfile {
+    fn default() -> Self {
+        LaserProfile {
+            cut_speed: 10.0,
+            layer_speeds: BTreeMap::new(),
+            pierce_time: 0.5,
+        }
+    }
+}
+
+impl LaserProfile {
+    pub fn speed(&self, layer: &str) -> f32 {
+        self.layer_speeds
+            .get(layer)
+            .cloned()
+            .unwrap_or(self.cut_speed)
+    }
+}
+
+/// Cutting on one layer of the document
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayerCut {
+    /// Stroke color of the paths
+    pub layer: String,
+    pub contours: usize,
+    /// Total length of the cut in mm
+    pub length: f32,
+    /// Cutting and piercing time in seconds
+    pub time: f32,
+}
+
+/// Estimated cost and machine time of the rack
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Estimate {
+    /// Layers in the order they appear in the document
+    pub layers: Vec<LayerCut>,
+    /// Area of the bounding boxes of the parts with clearance between them in mm²
+    pub material_area: f32,
+    pub sheets: usize,
+    pub sheet_size: SheetSize,
+    /// Price of the whole sheets needed
+    pub material_cost: f32,
+}
+
+impl Estimate {
+    /// Length of all cuts in mm
+    pub fn cut_length(&self) -> f32 {
+        self.layers.iter().map(|layer| layer.length).sum()
+    }
+
+    /// Machine time in seconds
+    pub fn cutting_time(&self) -> f32 {
+        self.layers.iter().map(|layer| layer.time).sum()
+    }
+}
+
+/// Total cut length of each layer, layers in the order they appear in the document
+pub fn layer_cuts(document: &Document, profile: &LaserProfile) -> Vec<LayerCut> {
+    let mut layers: Vec<LayerCut> = vec![];
+    for contour in document_contours(document) {
+        let speed = profile.speed(&contour.color);
+        let index = match layers.iter().position(|layer| layer.layer == contour.color) {
+            Some(index) => index,
+            None => {
+                layers.push(LayerCut {
+                    layer: contour.color.clone(),
+                    contours: 0,
+                    length: 0.0,
+                    time: 0.0,
+                });
+                layers.len() - 1
+            }
+        };
+        let layer = &mut layers[index];
+        let length = contour.length();
+        layer.contours += 1;
+        layer.length += length;
+        layer.time += length / speed + profile.pierce_time;
+    }
+    layers
+}
+
+/// Estimate material used, sheets needed, their price and the cutting time.
+///
+/// Whole sheets are paid, `price_per_square_meter` is usually taken from the material.
+pub fn estimate(
+    document: &Document,
+    sheet_size: &SheetSize,
+    price_per_square_meter: f32,
+    profile: &LaserProfile,
+) -> Result<Estimate, PartTooLarge> {
+    let clearance = CLEARANCE_BETWEEN_PATHS as f32;
+    let sizes = document_parts(document)
+        .iter()
+        .map(|part| {
+            let (min_x, min_y, max_x, max_y) = part.bounding_box();
+            (max_x - min_x, max_y - min_y)
+        })
+        .collect::<Vec<(f32, f32)>>();
+    let sheets = pack(&sizes, sheet_size)?
+        .iter()
+        .map(|placement| placement.sheet + 1)
+        .max()
+        .unwrap_or_default();
+    let sheet_area = sheet_size.width * sheet_size.height;
+
+    Ok(Estimate {
+        layers: layer_cuts(document, profile),
+        material_area: sizes
+            .iter()
+            .map(|(width, height)| (width + clearance) * (height + clearance))
+            .sum(),
+        sheets,
+        sheet_size: *sheet_size,
+        material_cost: sheets as f32 * sheet_area / 1_000_000.0 * price_per_square_meter,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rack::geometry::{contour_path, Contour};
+
+    fn contour(points: Vec<(f32, f32)>, closed: bool, color: &str) -> Contour {
+        Contour {
+            points,
+            closed,
+            color: color.to_string(),
+        }
+    }
+
+    fn square(x: f32, size: f32) -> Contour {
+        let points = vec![(x, 0.0), (x + size, 0.0), (x + size, size), (x, size)];
+        contour(points, true, "black")
+    }
+
+    #[test]
+    fn layer_time_is_cutting_at_layer_speed_and_piercing() {
+        let document = Document::new()
+            .add(contour_path(&square(0.0, 100.0)))
+            .add(contour_path(&contour(
+                vec![(10.0, 10.0), (60.0, 10.0)],
+                false,
+                "red",
+            )))
+            .add(contour_path(&square(200.0, 50.0)));
+        let profile = LaserProfile {
+            layer_speeds: BTreeMap::from([("red".to_string(), 5.0)]),
+            ..LaserProfile::default()
+        };
+
+        let layers = layer_cuts(&document, &profile);
+
+        assert_eq!(
+            layers,
+            vec![
+                LayerCut {
+                    layer: "black".to_string(),
+                    contours: 2,
+                    length: 600.0,
+                    time: 61.0,
+                },
+                LayerCut {
+                    layer: "red".to_string(),
+                    contours: 1,
+                    length: 50.0,
+                    time: 10.5,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn whole_sheets_are_paid() {
+        let document = Document::new()
+            .add(contour_path(&square(0.0, 300.0)))
+            .add(contour_path(&square(400.0, 300.0)));
+
+        let estimate = estimate(
+            &document,
+            &SheetSize::default(),
+            20.0,
+            &LaserProfile::default(),
+        )
+        .unwrap();
+
+        assert_eq!(estimate.sheets, 2);
+        let side = 300.0 + CLEARANCE_BETWEEN_PATHS as f32;
+        assert_eq!(estimate.material_area, 2.0 * side * side);
+        assert!((estimate.material_cost - 2.0 * 0.24 * 20.0).abs() < 0.001);
+        assert!((estimate.cutting_time() - (2.0 * 1200.0 / 10.0 + 1.0)).abs() < 0.001);
+    }
+
+    #[test]
+    fn part_larger_than_sheet_is_an_error() {
+        let document = Document::new().add(contour_path(&square(0.0, 500.0)));
+        let sheet = SheetSize::default();
+
+        assert!(estimate(&document, &sheet, 20.0, &LaserProfile::default()).is_err());
+    }
+}
@@ -0,0 +1,370 @@
+//! G-code output for CNC routers.
+//!
+//! Tool center moves outside of the outlines and inside of the holes by the tool radius.
+//! Parts are cut in several passes and the last passes leave tabs, so the parts stay in
+//! the sheet until the job is done.
+
+use std::fmt::Write;
+
+use svg::Document;
+
+use super::geometry::{distance, document_parts, document_size, offset_contour, Contour, Point};
+use super::LayoutError;
+
+/// Tool, depths and feeds of the router, all lengths in mm and feeds in mm/min
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct GcodeSettings {
+    pub tool_diameter: f32,
+    /// Total depth of the cut, usually the thickness of the material
+    pub cut_depth: f32,
+    pub depth_per_pass: f32,
+    pub feed_rate: f32,
+    pub plunge_rate: f32,
+    /// Spindle speed in RPM
+    pub spindle_speed: u32,
+    /// Height above the material for moves between cuts
+    pub safe_height: f32,
+    /// Number of tabs on the outline of every part, 0 for none
+    pub tabs: usize,
+    pub tab_width: f32,
+    pub tab_height: f32,
+}
+
+impl Default for GcodeSettings {
+    fn default() -> Self {
+        GcodeSettings {
+            tool_diameter: 3.175,
+            cut_depth: 4.0,
+            depth_per_pass: 1.5,
+            feed_rate: 1200.0,
+            plunge_rate: 300.0,
+            spindle_speed: 18000,
+            safe_height: 5.0,
+            tabs: 2,
+            tab_width: 6.0,
+            tab_height: 1.5,
+        }
+    }
+}
+
+impl GcodeSettings {
+    /// Depths of the passes from the first one to the full depth, top of the material is 0
+    fn pass_depths(&self) -> Vec<f32> {
+        let passes = (self.cut_depth / self.depth_per_pass).ceil().max(1.0) as usize;
+        (1..=passes)
+            .map(|pass| -(pass as f32 * self.depth_per_pass).min(self.cut_depth))
+            .collect()
+    }
+}
+
+/// Render paths of the SVG document as G-code.
+///
+/// Holes of a part are cut before its outline. Origin is the bottom left corner of the
+/// sheet and the top of the material, Y axis is flipped as in DXF. Open cuts, e.g. pieces
+/// left by common line, are cut along the line without the offset. Holes the bit doesn't fit
+/// in are an error.
+pub fn render_gcode(document: &Document, settings: &GcodeSettings) -> Result<String, LayoutError> {
+    let (_, height) = document_size(document);
+    let radius = settings.tool_diameter / 2.0;
+    let holes = narrow_holes(document, settings.tool_diameter);
+    if holes > 0 {
+        return Err(LayoutError::ToolDoesNotFit { holes });
+    }
+    let mut gcode = String::new();
+
+    writeln!(gcode, "(container rack)").unwrap();
+    writeln!(
+        gcode,
+        "(tool {}mm, depth {}mm in passes of {}mm)",
+        settings.tool_diameter, settings.cut_depth, settings.depth_per_pass
+    )
+    .unwrap();
+    writeln!(gcode, "G21").unwrap();
+    writeln!(gcode, "G90").unwrap();
+    writeln!(gcode, "G17").unwrap();
+    writeln!(gcode, "G0 Z{:.3}", settings.safe_height).unwrap();
+    writeln!(gcode, "M3 S{}", settings.spindle_speed).unwrap();
+
+    for part in document_parts(document) {
+        for hole in &part.holes {
+            cut_contour(
+                &mut gcode,
+                &offset_contour(hole, -radius),
+                height,
+                settings,
+                false,
+            );
+        }
+        cut_contour(
+            &mut gcode,
+            &offset_contour(&part.outline, radius),
+            height,
+            settings,
+            part.outline.closed,
+        );
+        for contour in &part.joined {
+            cut_contour(&mut gcode, contour, height, settings, false);
+        }
+    }
+
+    writeln!(gcode, "G0 Z{:.3}", settings.safe_height).unwrap();
+    writeln!(gcode, "M5").unwrap();
+    writeln!(gcode, "M30").unwrap();
+
+    Ok(gcode)
+}
+
+/// Number of holes in the document as narrow as the bit or narrower
+pub fn narrow_holes(document: &Document, tool_diameter: f32) -> usize {
+    document_parts(document)
+        .iter()
+        .flat_map(|part| &part.holes)
+        .filter(|hole| !tool_fits(hole, tool_diameter / 2.0))
+        .count()
+}
+
+/// Toolpath inside the hole keeps the direction of every edge. Where the bit is wider than
+/// the hole the edges turn around, where it is as wide the straight edges shrink to nothing.
+/// Arcs of reliefs are as round as the bit, so their short edges may shrink.
+fn tool_fits(hole: &Contour, radius: f32) -> bool {
+    let toolpath = offset_contour(hole, -radius);
+    let count = hole.points.len();
+    (0..count).all(|i| {
+        let (a, b) = (hole.points[i], hole.points[(i + 1) % count]);
+        let (c, d) = (toolpath.points[i], toolpath.points[(i + 1) % count]);
+        let length = distance(&a, &b);
+        let along = ((b.0 - a.0) * (d.0 - c.0) + (b.1 - a.1) * (d.1 - c.1)) / length.max(1e-6);
+        along > -0.01 && (length < radius || along > 0.01)
+    })
+}
+
+fn cut_contour(
+    gcode: &mut String,
+    contour: &Contour,
+    height: f32,
+    settings: &GcodeSettings,
+    with_tabs: bool,
+) {
+    let flip = |(x, y): Point| (x, height - y);
+    let mut points = contour
+        .points
+        .iter()
+        .map(|point| flip(*point))
+        .collect::<Vec<Point>>();
+    if points.len() < 2 {
+        return;
+    }
+    if contour.closed {
+        points.push(points[0]);
+    }
+    let length = points
+        .windows(2)
+        .map(|pair| distance(&pair[0], &pair[1]))
+        .sum::<f32>();
+    let tabs = if with_tabs {
+        tab_ranges(length, settings)
+    } else {
+        vec![]
+    };
+    let tab_depth = -settings.cut_depth + settings.tab_height;
+
+    let (x, y) = points[0];
+    writeln!(gcode, "G0 X{:.3} Y{:.3}", x, y).unwrap();
+    for depth in settings.pass_depths() {
+        if !contour.closed {
+            // Open cut starts again from its start point on every pass
+            writeln!(gcode, "G0 Z{:.3}", settings.safe_height).unwrap();
+            writeln!(gcode, "G0 X{:.3} Y{:.3}", x, y).unwrap();
+        }
+        writeln!(gcode, "G1 Z{:.3} F{}", depth, settings.plunge_rate).unwrap();
+        writeln!(gcode, "G1 F{}", settings.feed_rate).unwrap();
+
+        let tabs = if depth < tab_depth { &tabs[..] } else { &[] };
+        let mut travelled = 0.0;
+        for pair in points.windows(2) {
+            let (start, end) = (pair[0], pair[1]);
+            let edge = distance(&start, &end);
+            // Lift over the tabs and go back down after them
+            let mut changes = tabs
+                .iter()
+                .flat_map(|(from, to)| [(*from, tab_depth), (*to, depth)])
+                .filter(|(at, _)| *at > travelled && *at < travelled + edge)
+                .collect::<Vec<(f32, f32)>>();
+            changes.sort_by(|a, b| a.0.total_cmp(&b.0));
+            for (at, z) in changes {
+                let ratio = (at - travelled) / edge;
+                writeln!(
+                    gcode,
+                    "G1 X{:.3} Y{:.3}",
+                    start.0 + (end.0 - start.0) * ratio,
+                    start.1 + (end.1 - start.1) * ratio
+                )
+                .unwrap();
+                writeln!(gcode, "G1 Z{:.3}", z).unwrap();
+            }
+            writeln!(gcode, "G1 X{:.3} Y{:.3}", end.0, end.1).unwrap();
+            travelled += edge;
+        }
+    }
+    writeln!(gcode, "G0 Z{:.3}", settings.safe_height).unwrap();
+}
+
+/// Start and end of every tab along the toolpath, spread evenly.
+///
+/// Tool eats half of its diameter on both sides, so the gap in the path is wider than the tab.
+fn tab_ranges(length: f32, settings: &GcodeSettings) -> Vec<(f32, f32)> {
+    let width = settings.tab_width + settings.tool_diameter;
+    if settings.tabs == 0 || width * settings.tabs as f32 > length / 2.0 {
+        return vec![];
+    }
+
+    (0..settings.tabs)
+        .map(|tab| {
+            let center = (tab as f32 + 0.5) * length / settings.tabs as f32;
+            (center - width / 2.0, center + width / 2.0)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rack::geometry::{contour_path, remove_common_lines};
+    use crate::rack::relief::{relieve_holes, ReliefStyle};
+
+    fn rectangle(x: f32, y: f32, width: f32, height: f32) -> Contour {
+        Contour {
+            points: vec![
+                (x, y),
+                (x + width, y),
+                (x + width, y + height),
+                (x, y + height),
+            ],
+            closed: true,
+            color: "black".to_string(),
+        }
+    }
+
+    fn document(contours: &[Contour]) -> Document {
+        contours.iter().fold(
+            Document::new().set("viewBox", (0, 0, 200, 200)),
+            |document, contour| document.add(contour_path(contour)),
+        )
+    }
+
+    fn without_tabs() -> GcodeSettings {
+        GcodeSettings {
+            tabs: 0,
+            ..GcodeSettings::default()
+        }
+    }
+
+    #[test]
+    fn passes_go_down_to_the_cut_depth() {
+        assert_eq!(without_tabs().pass_depths(), vec![-1.5, -3.0, -4.0]);
+
+        let gcode = render_gcode(
+            &document(&[rectangle(0.0, 0.0, 50.0, 50.0)]),
+            &without_tabs(),
+        )
+        .unwrap();
+        let plunges = gcode
+            .lines()
+            .filter(|line| line.starts_with("G1 Z") && line.ends_with(" F300"))
+            .collect::<Vec<&str>>();
+        assert_eq!(
+            plunges,
+            ["G1 Z-1.500 F300", "G1 Z-3.000 F300", "G1 Z-4.000 F300"]
+        );
+    }
+
+    #[test]
+    fn tabs_lift_the_bit_on_the_deep_passes() {
+        let settings = GcodeSettings::default();
+        let tabs = tab_ranges(400.0, &settings);
+        assert_eq!(tabs.len(), 2);
+        for ((from, to), center) in tabs.iter().zip([100.0, 300.0]) {
+            // Bit eats its radius on both sides of the tab
+            assert!((to - from - (6.0 + 3.175)).abs() < 1e-4);
+            assert!(((from + to) / 2.0 - center).abs() < 1e-4);
+        }
+
+        let gcode =
+            render_gcode(&document(&[rectangle(0.0, 0.0, 100.0, 100.0)]), &settings).unwrap();
+        // Passes at 3mm and 4mm go under the top of the tabs at 2.5mm
+        let lifts = gcode.lines().filter(|line| *line == "G1 Z-2.500").count();
+        assert_eq!(lifts, 2 * 2);
+    }
+
+    #[test]
+    fn tabs_are_left_out_on_short_toolpath() {
+        assert!(tab_ranges(30.0, &GcodeSettings::default()).is_empty());
+    }
+
+    #[test]
+    fn toolpath_goes_outside_of_outline_and_inside_of_holes() {
+        let settings = GcodeSettings {
+            tool_diameter: 4.0,
+            ..without_tabs()
+        };
+        let gcode = render_gcode(
+            &document(&[
+                rectangle(0.0, 0.0, 100.0, 100.0),
+                rectangle(40.0, 40.0, 20.0, 20.0),
+            ]),
+            &settings,
+        )
+        .unwrap();
+
+        // Hole is cut first, Y is flipped
+        let starts = gcode
+            .lines()
+            .filter(|line| line.starts_with("G0 X"))
+            .collect::<Vec<&str>>();
+        assert_eq!(starts, ["G0 X42.000 Y158.000", "G0 X-2.000 Y202.000"]);
+    }
+
+    #[test]
+    fn common_line_pieces_are_cut_along_the_line() {
+        let document = remove_common_lines(&document(&[
+            rectangle(0.0, 0.0, 10.0, 10.0),
+            rectangle(0.0, 10.0, 10.0, 10.0),
+        ]));
+        let gcode = render_gcode(&document, &without_tabs()).unwrap();
+
+        // Open piece starts on the shared corner without offset, again on every pass
+        let starts = gcode
+            .lines()
+            .filter(|line| *line == "G0 X10.000 Y190.000")
+            .count();
+        assert_eq!(starts, 1 + 3);
+        assert!(gcode.contains("G1 X0.000 Y190.000"));
+    }
+
+    #[test]
+    fn bit_as_wide_as_a_hole_is_an_error() {
+        let slot = document(&[
+            rectangle(0.0, 0.0, 100.0, 100.0),
+            rectangle(20.0, 20.0, 4.0, 30.0),
+        ]);
+        let settings = |tool_diameter| GcodeSettings {
+            tool_diameter,
+            ..GcodeSettings::default()
+        };
+        assert_eq!(
+            render_gcode(&slot, &settings(4.0)),
+            Err(LayoutError::ToolDoesNotFit { holes: 1 })
+        );
+        assert_eq!(narrow_holes(&slot, 8.0), 1);
+        assert!(render_gcode(&slot, &settings(3.175)).is_ok());
+
+        // Arcs of the reliefs are as round as the bit
+        let relieved = relieve_holes(&slot, ReliefStyle::Dogbone, 3.175);
+        assert_eq!(narrow_holes(&relieved, 3.175), 0);
+    }
+}
@@ -0,0 +1,721 @@
+//! Geometry of the generated rack, read back from the paths of the SVG document.
+
+use svg::node::element::path::{Command, Data, Position};
+use svg::node::element::{Element, Path};
+use svg::{Document, Node};
+
+pub type Point = (f32, f32);
+
+/// Continuous line of a path, closed contours are cut out as parts or holes
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contour {
+    pub points: Vec<Point>,
+    pub closed: bool,
+    /// Stroke color of the path, defines the layer and cutting order
+    pub color: String,
+}
+
+impl Contour {
+    /// Length of the cut along the contour, closed contour includes the line back to start
+    pub fn length(&self) -> f32 {
+        let closing = match (self.closed, self.points.first(), self.points.last()) {
+            (true, Some(first), Some(last)) => distance(last, first),
+            _ => 0.0,
+        };
+
+        self.points
+            .windows(2)
+            .map(|pair| distance(&pair[0], &pair[1]))
+            .sum::<f32>()
+            + closing
+    }
+
+    /// Area enclosed by the contour, open contour has none
+    pub fn area(&self) -> f32 {
+        if !self.closed {
+            return 0.0;
+        }
+        let points = &self.points;
+        let twice_area: f32 = (0..points.len())
+            .map(|index| {
+                let (x1, y1) = points[index];
+                let (x2, y2) = points[(index + 1) % points.len()];
+                x1 * y2 - x2 * y1
+            })
+            .sum();
+
+        twice_area.abs() / 2.0
+    }
+}
+
+/// Straight distance between two points
+pub fn distance((x1, y1): &Point, (x2, y2): &Point) -> f32 {
+    ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt()
+}
+
+/// All contours of the document in the order they are in the document
+pub fn document_contours(document: &Document) -> Vec<Contour> {
+    let mut contours = vec![];
+    collect_contours(document, &mut contours);
+    contours
+}
+
+fn collect_contours(node: &dyn Node, contours: &mut Vec<Contour>) {
+    if node.get_name() == "path" {
+        if let Some(attributes) = node.get_attributes() {
+            let color = attributes
+                .get("stroke")
+                .map(|color| color.to_string())
+                .unwrap_or_default();
+            if let Some(Ok(data)) = attributes.get("d").map(|d| Data::parse(d)) {
+                contours.extend(data_contours(&data, &color));
+            }
+        }
+    }
+
+    if let Some(children) = node.get_children() {
+        children
+            .iter()
+            .for_each(|child| collect_contours(child.as_ref(), contours));
+    }
+}
+
+/// Split path data to contours. Curves are replaced with a line to their end point.
+pub fn data_contours(data: &Data, color: &str) -> Vec<Contour> {
+    let mut contours = vec![];
+    let mut points: Vec<Point> = vec![];
+    let mut current: Point = (0.0, 0.0);
+
+    let mut finish = |points: &mut Vec<Point>, closed: bool| {
+        if points.len() > 1 {
+            contours.push(Contour {
+                points: std::mem::take(points),
+                closed,
+                color: color.to_string(),
+            });
+        }
+        points.clear();
+    };
+
+    for command in data.iter() {
+        match command {
+            Command::Move(position, parameters) => {
+                finish(&mut points, false);
+                // Extra coordinate pairs after the first one are lines
+                for point in parameters.chunks(2) {
+                    current = resolve(position, current, point[0], point[1]);
+                    points.push(current);
+                }
+            }
+            Command::Line(position, parameters) => {
+                for point in parameters.chunks(2) {
+                    current = resolve(position, current, point[0], point[1]);
+                    points.push(current);
+                }
+            }
+            Command::HorizontalLine(position, parameters) => {
+                for x in parameters.iter() {
+                    current = match position {
+                        Position::Absolute => (*x, current.1),
+                        Position::Relative => (current.0 + x, current.1),
+                    };
+                    points.push(current);
+                }
+            }
+            Command::VerticalLine(position, parameters) => {
+                for y in parameters.iter() {
+                    current = match position {
+                        Position::Absolute => (current.0, *y),
+                        Position::Relative => (current.0, current.1 + y),
+                    };
+                    points.push(current);
+                }
+            }
+            Command::Close => {
+                let start = points.first().cloned();
+                finish(&mut points, true);
+                if let Some(start) = start {
+                    current = start;
+                }
+            }
+            Command::QuadraticCurve(position, parameters)
+            | Command::SmoothQuadraticCurve(position, parameters)
+            | Command::CubicCurve(position, parameters)
+            | Command::SmoothCubicCurve(position, parameters)
+            | Command::EllipticalArc(position, parameters) => {
+                if parameters.len() >= 2 {
+                    let end = &parameters[parameters.len() - 2..];
+                    current = resolve(position, current, end[0], end[1]);
+                    points.push(current);
+                }
+            }
+        }
+    }
+    finish(&mut points, false);
+
+    contours
+}
+
+fn resolve(position: &Position, current: Point, x: f32, y: f32) -> Point {
+    match position {
+        Position::Absolute => (x, y),
+        Position::Relative => (current.0 + x, current.1 + y),
+    }
+}
+
+/// Width and height of the document from its view box
+pub fn document_size(document: &Document) -> Point {
+    document
+        .get_attributes()
+        .and_then(|attributes| attributes.get("viewBox"))
+        .map(|view_box| {
+            let values = view_box
+                .split([' ', ','])
+                .filter_map(|value| value.parse::<f32>().ok())
+                .collect::<Vec<f32>>();
+            match values.as_slice() {
+                [_, _, width, height] => (*width, *height),
+                _ => (0.0, 0.0),
+            }
+        })
+        .unwrap_or_default()
+}
+
+/// Smallest rectangle containing the points, as `(min_x, min_y, max_x, max_y)`
+pub fn bounding_box<'a>(points: impl IntoIterator<Item = &'a Point>) -> (f32, f32, f32, f32) {
+    points.into_iter().fold(
+        (
+            f32::INFINITY,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            f32::NEG_INFINITY,
+        ),
+        |(min_x, min_y, max_x, max_y), (x, y)| {
+            (min_x.min(*x), min_y.min(*y), max_x.max(*x), max_y.max(*y))
+        },
+    )
+}
+
+/// Single piece cut out of the sheet: outer cut and holes inside it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Part {
+    pub outline: Contour,
+    pub holes: Vec<Contour>,
+    /// Open cuts of neighbouring pieces ending on the outline, e.g. wings sharing a common
+    /// line. They are finished by the outline, so they stay where they are next to it.
+    pub joined: Vec<Contour>,
+    /// Text engraved on the part, e.g. a label
+    pub engravings: Vec<Engraving>,
+}
+
+/// SVG `text` element and its anchor point from the `x` and `y` attributes
+#[derive(Debug, Clone)]
+pub struct Engraving {
+    pub position: Point,
+    pub element: Element,
+}
+
+impl PartialEq for Engraving {
+    fn eq(&self, other: &Self) -> bool {
+        self.position == other.position && self.element.to_string() == other.element.to_string()
+    }
+}
+
+impl Part {
+    /// Part of a single closed outline and its holes
+    pub fn new(outline: Contour, holes: Vec<Contour>) -> Self {
+        Part {
+            outline,
+            holes,
+            joined: vec![],
+            engravings: vec![],
+        }
+    }
+
+    pub fn bounding_box(&self) -> (f32, f32, f32, f32) {
+        bounding_box(
+            std::iter::once(&self.outline)
+                .chain(&self.joined)
+                .flat_map(|contour| &contour.points),
+        )
+    }
+
+    /// All contours, holes first as they have to be cut before the outline
+    pub fn contours(&self) -> impl Iterator<Item = &Contour> {
+        self.holes
+            .iter()
+            .chain(std::iter::once(&self.outline))
+            .chain(&self.joined)
+    }
+
+    /// Move and optionally turn the part 90 degrees, so its bounding box starts at `(x, y)`
+    pub fn place(&self, x: f32, y: f32, rotated: bool) -> Part {
+        let (min_x, min_y, max_x, _) = self.bounding_box();
+        let move_point = |(px, py): &Point| {
+            let (dx, dy) = (px - min_x, py - min_y);
+            if rotated {
+                (x + dy, y + (max_x - min_x) - dx)
+            } else {
+                (x + dx, y + dy)
+            }
+        };
+        let transform = |contour: &Contour| Contour {
+            points: contour.points.iter().map(move_point).collect(),
+            closed: contour.closed,
+            color: contour.color.clone(),
+        };
+        // Text turns with the part around its anchor
+        let engrave = |engraving: &Engraving| {
+            let position = move_point(&engraving.position);
+            let mut element = engraving.element.clone();
+            element.assign("x", position.0);
+            element.assign("y", position.1);
+            if rotated {
+                element.assign(
+                    "transform",
+                    format!("rotate(-90 {} {})", position.0, position.1),
+                );
+            }
+            Engraving { position, element }
+        };
+
+        Part {
+            outline: transform(&self.outline),
+            holes: self.holes.iter().map(transform).collect(),
+            joined: self.joined.iter().map(transform).collect(),
+            engravings: self.engravings.iter().map(engrave).collect(),
+        }
+    }
+}
+
+/// Group contours of the document to parts, contour inside another one is a hole in it.
+/// Text goes to the part whose outline is around its anchor, text outside of parts is left out.
+///
+/// Open contour ending on a contour of an earlier part is joined to that part, it's the rest
+/// of a piece whose line is cut with the part (see `remove_common_lines`).
+pub fn document_parts(document: &Document) -> Vec<Part> {
+    let contours = document_contours(document);
+    let boxes = contours
+        .iter()
+        .map(|contour| bounding_box(&contour.points))
+        .collect::<Vec<_>>();
+    let inside = |inner: usize, outer: usize| {
+        let (a, b) = (boxes[inner], boxes[outer]);
+        inner != outer
+            && contours[outer].closed
+            && a.0 >= b.0
+            && a.1 >= b.1
+            && a.2 <= b.2
+            && a.3 <= b.3
+            && a != b
+    };
+    let area = |index: usize| (boxes[index].2 - boxes[index].0) * (boxes[index].3 - boxes[index].1);
+    // Smallest contour around each contour, if there is one
+    let parents = (0..contours.len())
+        .map(|inner| {
+            (0..contours.len())
+                .filter(|outer| inside(inner, *outer))
+                .min_by(|a, b| area(*a).total_cmp(&area(*b)))
+        })
+        .collect::<Vec<Option<usize>>>();
+
+    let mut parts = contours
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| parents[*index].is_none())
+        .map(|(index, contour)| (index, Part::new(contour.clone(), vec![])))
+        .collect::<Vec<(usize, Part)>>();
+
+    for (index, contour) in contours.iter().enumerate() {
+        // Holes in holes are rare, they go to the outermost part
+        let mut root = parents[index];
+        while let Some(parent) = root.and_then(|parent| parents[parent]) {
+            root = Some(parent);
+        }
+        if let Some(part) = root.and_then(|root| parts.iter_mut().find(|(i, _)| *i == root)) {
+            part.1.holes.push(contour.clone());
+        }
+    }
+
+    let mut joined_parts: Vec<Part> = vec![];
+    for (_, part) in parts {
+        let ends = [part.outline.points.first(), part.outline.points.last()];
+        let target = joined_parts.iter_mut().find(|target| {
+            !part.outline.closed
+                && part.holes.is_empty()
+                && ends.iter().flatten().any(|end| {
+                    std::iter::once(&target.outline)
+                        .chain(&target.joined)
+                        .any(|contour| on_contour(end, contour))
+                })
+        });
+        match target {
+            Some(target) => target.joined.push(part.outline),
+            None => joined_parts.push(part),
+        }
+    }
+
+    for engraving in document_engravings(document) {
+        let (x, y) = engraving.position;
+        let part = joined_parts.iter_mut().find(|part| {
+            let (min_x, min_y, max_x, max_y) = bounding_box(&part.outline.points);
+            part.outline.closed && x >= min_x && x <= max_x && y >= min_y && y <= max_y
+        });
+        if let Some(part) = part {
+            part.engravings.push(engraving);
+        }
+    }
+
+    joined_parts
+}
+
+/// Text elements at the top level of the document
+fn document_engravings(document: &Document) -> Vec<Engraving> {
+    let coordinate = |node: &dyn Node, name: &str| {
+        node.get_attributes()
+            .and_then(|attributes| attributes.get(name))
+            .and_then(|value| value.parse::<f32>().ok())
+    };
+
+    document
+        .get_children()
+        .into_iter()
+        .flatten()
+        .filter(|child| child.get_name() == "text")
+        .filter_map(|child| {
+            let position = (
+                coordinate(child.as_ref(), "x")?,
+                coordinate(child.as_ref(), "y")?,
+            );
+            let mut element = Element::new("text");
+            for (name, value) in child.get_attributes()? {
+                element.assign(name.as_str(), value.clone());
+            }
+            for grandchild in child.get_children().into_iter().flatten() {
+                element.append(grandchild.clone());
+            }
+            Some(Engraving { position, element })
+        })
+        .collect()
+}
+
+/// Point lies on a line of the contour, within 0.01mm
+fn on_contour(point: &Point, contour: &Contour) -> bool {
+    let count = contour.points.len();
+    let lines = if contour.closed { count } else { count - 1 };
+    (0..lines).any(|index| {
+        let (a, b) = (contour.points[index], contour.points[(index + 1) % count]);
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let length = dx * dx + dy * dy;
+        let along = if length == 0.0 {
+            0.0
+        } else {
+            (((point.0 - a.0) * dx + (point.1 - a.1) * dy) / length).clamp(0.0, 1.0)
+        };
+        distance(point, &(a.0 + along * dx, a.1 + along * dy)) < 0.01
+    })
+}
+
+/// SVG path of the contour
+pub fn contour_path(contour: &Contour) -> Path {
+    Path::new()
+        .set("fill", "none")
+        .set("stroke", contour.color.as_str())
+        .set("d", contour_data(contour))
+}
+
+/// Path data of the contour, straight lines between the points
+pub fn contour_data(contour: &Contour) -> Data {
+    let mut data = Data::new();
+    for (index, point) in contour.points.iter().enumerate() {
+        data = if index == 0 {
+            data.move_to(*point)
+        } else {
+            data.line_to(*point)
+        };
+    }
+    if contour.closed {
+        data = data.close();
+    }
+    data
+}
+
+/// Move every edge of a closed contour by `distance`, positive grows the contour.
+///
+/// Corners are mitered, which is exact for the right angles used in the rack.
+pub fn offset_contour(contour: &Contour, distance: f32) -> Contour {
+    let points = &contour.points;
+    if !contour.closed || points.len() < 3 || distance == 0.0 {
+        return contour.clone();
+    }
+
+    // Outward normal is on the right side of counter clockwise contour
+    let signed_area: f32 = (0..points.len())
+        .map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % points.len()]);
+            a.0 * b.1 - b.0 * a.1
+        })
+        .sum();
+    let side = if signed_area > 0.0 { 1.0 } else { -1.0 };
+    let normal = |a: Point, b: Point| {
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length == 0.0 {
+            (0.0, 0.0)
+        } else {
+            (side * dy / length, -side * dx / length)
+        }
+    };
+
+    let count = points.len();
+    let offset_points = (0..count)
+        .map(|i| {
+            let previous = points[(i + count - 1) % count];
+            let point = points[i];
+            let next = points[(i + 1) % count];
+            let (n1, n2) = (normal(previous, point), normal(point, next));
+            let dot = n1.0 * n2.0 + n1.1 * n2.1;
+            let (x, y) = if dot > -0.99 {
+                ((n1.0 + n2.0) / (1.0 + dot), (n1.1 + n2.1) / (1.0 + dot))
+            } else {
+                n1
+            };
+            (point.0 + distance * x, point.1 + distance * y)
+        })
+        .collect();
+
+    Contour {
+        points: offset_points,
+        closed: true,
+        color: contour.color.clone(),
+    }
+}
+
+/// Compensate for the material burned away by the laser: outlines grow and holes shrink by
+/// half of the kerf, so parts end up with their designed size
+pub fn compensate_kerf(document: &Document, kerf: f32) -> Document {
+    let mut compensated = empty_copy(document);
+    for part in document_parts(document) {
+        for hole in &part.holes {
+            compensated.append(contour_path(&offset_contour(hole, -kerf / 2.0)));
+        }
+        compensated.append(contour_path(&offset_contour(&part.outline, kerf / 2.0)));
+        for contour in &part.joined {
+            compensated.append(contour_path(contour));
+        }
+    }
+    compensated
+}
+
+/// Keep lines shared by two contours only in the first one.
+///
+/// Contour with a shared line is split to open contours around it, so the line is cut once.
+pub fn remove_common_lines(document: &Document) -> Document {
+    let mut result = empty_copy(document);
+    let mut cut_lines: Vec<(Point, Point)> = vec![];
+
+    for contour in document_contours(document) {
+        let points = &contour.points;
+        let edges = if contour.closed {
+            points.len()
+        } else {
+            points.len() - 1
+        };
+        let edge = |index: usize| (points[index], points[(index + 1) % points.len()]);
+        let shared = (0..edges)
+            .map(|index| cut_lines.iter().any(|line| same_line(line, &edge(index))))
+            .collect::<Vec<bool>>();
+        cut_lines.extend((0..edges).map(edge));
+
+        if !shared.contains(&true) {
+            result.append(contour_path(&contour));
+            continue;
+        }
+
+        // Closed contour starts after a shared edge, so no open piece wraps around its start
+        let first = if contour.closed {
+            (shared.iter().position(|shared| *shared).unwrap() + 1) % edges
+        } else {
+            0
+        };
+        let mut piece: Vec<Point> = vec![];
+        for index in (0..edges).map(|offset| (first + offset) % edges) {
+            if shared[index] {
+                finish_piece(&mut result, &mut piece, &contour.color);
+            } else {
+                let (start, end) = edge(index);
+                if piece.is_empty() {
+                    piece.push(start);
+                }
+                piece.push(end);
+            }
+        }
+        finish_piece(&mut result, &mut piece, &contour.color);
+    }
+
+    result
+}
+
+fn finish_piece(document: &mut Document, piece: &mut Vec<Point>, color: &str) {
+    if piece.len() > 1 {
+        document.append(contour_path(&Contour {
+            points: std::mem::take(piece),
+            closed: false,
+            color: color.to_string(),
+        }));
+    }
+    piece.clear();
+}
+
+/// Lines with the same end points in either direction, within 0.01mm
+fn same_line(a: &(Point, Point), b: &(Point, Point)) -> bool {
+    let same = |p: &Point, q: &Point| distance(p, q) < 0.01;
+    (same(&a.0, &b.0) && same(&a.1, &b.1)) || (same(&a.0, &b.1) && same(&a.1, &b.0))
+}
+
+/// Document with same attributes (size, view box) and engraved text but without paths
+pub fn empty_copy(document: &Document) -> Document {
+    let mut copy = Document::new();
+    if let Some(attributes) = document.get_attributes() {
+        for (name, value) in attributes {
+            copy.assign(name.as_str(), value.clone());
+        }
+    }
+    if let Some(children) = document.get_children() {
+        children
+            .iter()
+            .filter(|child| child.get_name() != "path")
+            .for_each(|child| copy.append(child.clone()));
+    }
+    copy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f32, y: f32, size: f32) -> Contour {
+        Contour {
+            points: vec![(x, y), (x + size, y), (x + size, y + size), (x, y + size)],
+            closed: true,
+            color: "black".to_string(),
+        }
+    }
+
+    fn document(contours: &[Contour]) -> Document {
+        contours.iter().fold(Document::new(), |document, contour| {
+            document.add(contour_path(contour))
+        })
+    }
+
+    #[test]
+    fn document_parts_puts_contours_inside_outline_to_holes() {
+        let parts = document_parts(&document(&[
+            square(0.0, 0.0, 100.0),
+            square(10.0, 10.0, 5.0),
+            square(200.0, 0.0, 50.0),
+            square(50.0, 50.0, 5.0),
+        ]));
+
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].outline, square(0.0, 0.0, 100.0));
+        assert_eq!(
+            parts[0].holes,
+            vec![square(10.0, 10.0, 5.0), square(50.0, 50.0, 5.0)]
+        );
+        assert_eq!(parts[1].outline, square(200.0, 0.0, 50.0));
+        assert!(parts[1].holes.is_empty());
+    }
+
+    #[test]
+    fn document_parts_keeps_hole_in_hole_in_outermost_part() {
+        let parts = document_parts(&document(&[
+            square(0.0, 0.0, 100.0),
+            square(10.0, 10.0, 50.0),
+            square(20.0, 20.0, 5.0),
+        ]));
+
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].holes.len(), 2);
+    }
+
+    #[test]
+    fn offset_contour_grows_and_shrinks_square_in_either_direction() {
+        let clockwise = Contour {
+            points: square(0.0, 0.0, 10.0).points.into_iter().rev().collect(),
+            ..square(0.0, 0.0, 10.0)
+        };
+        for contour in [square(0.0, 0.0, 10.0), clockwise] {
+            let grown = offset_contour(&contour, 1.0);
+            assert_eq!(bounding_box(&grown.points), (-1.0, -1.0, 11.0, 11.0));
+            let shrunk = offset_contour(&contour, -1.0);
+            assert_eq!(bounding_box(&shrunk.points), (1.0, 1.0, 9.0, 9.0));
+        }
+    }
+
+    #[test]
+    fn offset_contour_leaves_open_contour() {
+        let open = Contour {
+            closed: false,
+            ..square(0.0, 0.0, 10.0)
+        };
+        assert_eq!(offset_contour(&open, 1.0), open);
+    }
+
+    #[test]
+    fn compensate_kerf_grows_outline_and_shrinks_holes() {
+        let compensated = compensate_kerf(
+            &document(&[square(0.0, 0.0, 100.0), square(10.0, 10.0, 20.0)]),
+            0.2,
+        );
+
+        let parts = document_parts(&compensated);
+        assert_eq!(parts.len(), 1);
+        let (min_x, _, max_x, _) = parts[0].bounding_box();
+        assert!((max_x - min_x - 100.2).abs() < 1e-4);
+        let (min_x, _, max_x, _) = bounding_box(&parts[0].holes[0].points);
+        assert!((max_x - min_x - 19.8).abs() < 1e-4);
+    }
+
+    #[test]
+    fn remove_common_lines_cuts_shared_line_once() {
+        let document = remove_common_lines(&document(&[
+            square(0.0, 0.0, 10.0),
+            square(0.0, 10.0, 10.0),
+        ]));
+
+        let contours = document_contours(&document);
+        assert_eq!(contours.len(), 2);
+        assert_eq!(contours[0], square(0.0, 0.0, 10.0));
+        assert!(!contours[1].closed);
+        assert_eq!(
+            contours[1].points,
+            vec![(10.0, 10.0), (10.0, 20.0), (0.0, 20.0), (0.0, 10.0)]
+        );
+    }
+
+    #[test]
+    fn remove_common_lines_keeps_contours_without_shared_lines() {
+        let contours = [square(0.0, 0.0, 10.0), square(20.0, 0.0, 10.0)];
+
+        let document = remove_common_lines(&document(&contours));
+
+        assert_eq!(document_contours(&document), contours);
+    }
+
+    #[test]
+    fn document_parts_joins_common_line_piece_to_its_neighbour() {
+        let document = remove_common_lines(&document(&[
+            square(0.0, 0.0, 10.0),
+            square(0.0, 10.0, 10.0),
+            square(50.0, 0.0, 10.0),
+        ]));
+
+        let parts = document_parts(&document);
+
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].joined.len(), 1);
+        assert_eq!(parts[0].bounding_box(), (0.0, 0.0, 10.0, 20.0));
+        assert!(parts[1].joined.is_empty());
+    }
+}
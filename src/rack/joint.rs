@@ -0,0 +1,704 @@
+//! Joints between the parts of the rack.
+//!
+//! Joint style draws both sides of a joint along an edge: the male profile with the tabs and
+//! the female profile with the notches they go into. Part the male part goes through, e.g.
+//! a cover for the inner side panels, gets slots instead of notches.
+//!
+//! Profiles are in edge coordinates `(along, out)`. `along` runs from 0 to the length of the
+//! edge, `out` is 0 on the outer face of the joint and negative inside the parts. Male part
+//! ends at `out = -thickness` and its tabs reach 0, female part ends at 0 and its notches go
+//! down to `-thickness`. `place` moves a profile onto the edge of a part.
+
+use std::f32::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+
+use super::geometry::Point;
+use super::{
+    SIDE_TAP_FROM_FRONT, SIDE_TAP_WIDTH, SIDE_WING_SLOT_FROM_FRONT, SIDE_WING_SLOT_SPACING,
+    SIDE_WING_SLOT_WIDTH,
+};
+
+/// Fingers are about this many material thicknesses wide
+const FINGER_WIDTH_IN_THICKNESSES: f32 = 5.0;
+
+/// Shape of the joint along an edge, implement it for own joints
+pub trait JointStyle: fmt::Debug + Send + Sync {
+    /// Start and end of every tab along the edge
+    fn tabs(&self, length: f32, material_thickness: f32) -> Vec<(f32, f32)>;
+
+    /// Profile of the part with the tabs, from `along = 0` to `along = length`
+    fn male(&self, length: f32, material_thickness: f32) -> Vec<Point> {
+        rectangular_profile(
+            &self.tabs(length, material_thickness),
+            length,
+            -material_thickness,
+            0.0,
+        )
+    }
+
+    /// Profile of the part with the notches, from `along = 0` to `along = length`
+    fn female(&self, length: f32, material_thickness: f32) -> Vec<Point> {
+        rectangular_profile(
+            &self.tabs(length, material_thickness),
+            length,
+            0.0,
+            -material_thickness,
+        )
+    }
+
+    /// Holes in a part the male part goes through, `material_thickness` is the width of
+    /// the slot
+    fn slots(&self, length: f32, material_thickness: f32) -> Vec<Vec<Point>> {
+        self.tabs(length, material_thickness)
+            .into_iter()
+            .map(|(from, to)| {
+                vec![
+                    (from, 0.0),
+                    (to, 0.0),
+                    (to, -material_thickness),
+                    (from, -material_thickness),
+                ]
+            })
+            .collect()
+    }
+
+    /// Other holes in the female part or the part with the slots, e.g. for bolts
+    fn female_holes(&self, _length: f32, _material_thickness: f32) -> Vec<Vec<Point>> {
+        vec![]
+    }
+}
+
+/// Profile with rectangular tabs or notches, `base` is the level between them and `top`
+/// the level of the tabs
+fn rectangular_profile(tabs: &[(f32, f32)], length: f32, base: f32, top: f32) -> Vec<Point> {
+    let mut profile = vec![(0.0, base)];
+    for (from, to) in tabs {
+        profile.extend([(*from, base), (*from, top), (*to, top), (*to, base)]);
+    }
+    profile.push((length, base));
+    profile
+}
+
+/// Points of the profile on the edge of a part from `start` to `end`.
+///
+/// Outside of the part is on the right when looking from `start` to `end` on the sheet, so
+/// outlines drawn counterclockwise on the sheet get the joint facing out.
+pub fn place(profile: &[Point], start: Point, end: Point) -> Vec<Point> {
+    let length = ((end.0 - start.0).powi(2) + (end.1 - start.1).powi(2)).sqrt();
+    let direction = if length == 0.0 {
+        (0.0, 0.0)
+    } else {
+        ((end.0 - start.0) / length, (end.1 - start.1) / length)
+    };
+    let outward = (-direction.1, direction.0);
+
+    profile
+        .iter()
+        .map(|(along, out)| {
+            (
+                start.0 + along * direction.0 + out * outward.0,
+                start.1 + along * direction.1 + out * outward.1,
+            )
+        })
+        .collect()
+}
+
+/// Two straight tabs, one near each end of the edge
+#[derive(Debug, Clone, PartialEq)]
+pub struct StraightTabs {
+    /// Distance of the tab from the end of the edge
+    pub from_end: f32,
+    pub width: f32,
+}
+
+impl Default for StraightTabs {
+    fn default() -> Self {
+        StraightTabs {
+            from_end: SIDE_TAP_FROM_FRONT as f32,
+            width: SIDE_TAP_WIDTH as f32,
+        }
+    }
+}
+
+impl JointStyle for StraightTabs {
+    fn tabs(&self, length: f32, _material_thickness: f32) -> Vec<(f32, f32)> {
+        vec![
+            (self.from_end, self.from_end + self.width),
+            (length - self.from_end - self.width, length - self.from_end),
+        ]
+    }
+}
+
+/// Tabs of the side wings going into the side panels, two near the front and two near the
+/// back. Wings of a pair take every other tab, so the pair is cut with the tabs interlocked
+#[derive(Debug, Clone, PartialEq)]
+pub struct WingTabs {
+    /// Distance of the outer tabs from the ends of the edge
+    pub from_end: f32,
+    pub width: f32,
+    /// Gap between the two tabs at each end
+    pub spacing: f32,
+}
+
+impl Default for WingTabs {
+    fn default() -> Self {
+        WingTabs {
+            from_end: SIDE_WING_SLOT_FROM_FRONT as f32,
+            width: SIDE_WING_SLOT_WIDTH as f32,
+            spacing: SIDE_WING_SLOT_SPACING as f32,
+        }
+    }
+}
+
+impl JointStyle for WingTabs {
+    fn tabs(&self, length: f32, _material_thickness: f32) -> Vec<(f32, f32)> {
+        let inner = self.from_end + self.width + self.spacing;
+        vec![
+            (self.from_end, self.from_end + self.width),
+            (inner, inner + self.width),
+            (length - inner - self.width, length - inner),
+            (length - self.from_end - self.width, length - self.from_end),
+        ]
+    }
+}
+
+/// Evenly spaced fingers along the full edge, count follows from the length and thickness
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FingerJoint;
+
+impl JointStyle for FingerJoint {
+    fn tabs(&self, length: f32, material_thickness: f32) -> Vec<(f32, f32)> {
+        // Female part keeps the fingers at both ends, so the corners are solid
+        let count = finger_count(length as usize, material_thickness);
+        let width = length / count as f32;
+        (1..count)
+            .step_by(2)
+            .map(|finger| (finger as f32 * width, (finger + 1) as f32 * width))
+            .collect()
+    }
+}
+
+/// Odd number of fingers along the depth, at least 3
+pub fn finger_count(depth: usize, material_thickness: f32) -> usize {
+    let finger_width = (FINGER_WIDTH_IN_THICKNESSES * material_thickness).max(1.0);
+    let count = ((depth as f32 / finger_width) as usize).max(3);
+    if count.is_multiple_of(2) {
+        count - 1
+    } else {
+        count
+    }
+}
+
+/// Fingers wider at the tip than at the base. They lock only when both parts are in the same
+/// plane, e.g. for joining two halves of a part longer than the sheet, `in_plane_female` is
+/// the other half. In the corners of the rack the notches and slots take the full tips
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dovetail {
+    /// Angle of the sides of the tail from straight, in degrees
+    pub angle: f32,
+}
+
+impl Default for Dovetail {
+    fn default() -> Self {
+        Dovetail { angle: 10.0 }
+    }
+}
+
+impl Dovetail {
+    /// How much wider the tail is on each side at the tip than at the base
+    pub fn spread(&self, material_thickness: f32) -> f32 {
+        material_thickness * (self.angle * PI / 180.0).tan()
+    }
+
+    /// Profile of the part the tails lock into when both parts are in the same plane
+    pub fn in_plane_female(&self, length: f32, material_thickness: f32) -> Vec<Point> {
+        self.profile(length, material_thickness, 0.0, -material_thickness)
+    }
+
+    fn profile(&self, length: f32, material_thickness: f32, base: f32, top: f32) -> Vec<Point> {
+        let spread = self.spread(material_thickness);
+        let mut profile = vec![(0.0, base)];
+        for (from, to) in FingerJoint.tabs(length, material_thickness) {
+            profile.extend([(from + spread, base), (from - spread, top)]);
+            profile.extend([(to + spread, top), (to - spread, base)]);
+        }
+        profile.push((length, base));
+        profile
+    }
+}
+
+impl JointStyle for Dovetail {
+    /// Tails at their widest, the tips
+    fn tabs(&self, length: f32, material_thickness: f32) -> Vec<(f32, f32)> {
+        let spread = self.spread(material_thickness);
+        FingerJoint
+            .tabs(length, material_thickness)
+            .into_iter()
+            .map(|(from, to)| (from - spread, to + spread))
+            .collect()
+    }
+
+    fn male(&self, length: f32, material_thickness: f32) -> Vec<Point> {
+        self.profile(length, material_thickness, -material_thickness, 0.0)
+    }
+}
+
+/// Straight tabs with a bolt between them. Bolt goes through the female part into the end
+/// of the male part and holds a nut in a pocket cut into the male part
+#[derive(Debug, Clone, PartialEq)]
+pub struct TSlot {
+    pub tabs: StraightTabs,
+    pub bolt_diameter: f32,
+    /// Length of the bolt under its head
+    pub bolt_length: f32,
+    /// Width of the nut across flats
+    pub nut_width: f32,
+    pub nut_thickness: f32,
+}
+
+impl TSlot {
+    /// Center of every bolt along the edge, in the middle between neighbouring tabs
+    pub fn bolts(&self, length: f32, material_thickness: f32) -> Vec<f32> {
+        self.tabs(length, material_thickness)
+            .windows(2)
+            .map(|pair| (pair[0].1 + pair[1].0) / 2.0)
+            .collect()
+    }
+}
+
+impl JointStyle for TSlot {
+    fn tabs(&self, length: f32, material_thickness: f32) -> Vec<(f32, f32)> {
+        self.tabs.tabs(length, material_thickness)
+    }
+
+    fn male(&self, length: f32, material_thickness: f32) -> Vec<Point> {
+        let base = -material_thickness;
+        // Bolt goes through the female part, nut sits where the bolt ends
+        let stem =
+            (self.bolt_length - material_thickness - self.nut_thickness).max(material_thickness);
+        let (bolt, nut) = (self.bolt_diameter / 2.0, self.nut_width / 2.0);
+
+        let mut profile = vec![];
+        let mut bolts = self
+            .bolts(length, material_thickness)
+            .into_iter()
+            .peekable();
+        for point in self.tabs.male(length, material_thickness) {
+            if let Some(center) = bolts.next_if(|center| point.0 > *center) {
+                profile.extend([
+                    (center - bolt, base),
+                    (center - bolt, base - stem),
+                    (center - nut, base - stem),
+                    (center - nut, base - stem - self.nut_thickness),
+                    (center + nut, base - stem - self.nut_thickness),
+                    (center + nut, base - stem),
+                    (center + bolt, base - stem),
+                    (center + bolt, base),
+                ]);
+            }
+            profile.push(point);
+        }
+        profile
+    }
+
+    fn female_holes(&self, length: f32, material_thickness: f32) -> Vec<Vec<Point>> {
+        self.bolts(length, material_thickness)
+            .into_iter()
+            .map(|center| {
+                circle(
+                    (center, -material_thickness / 2.0),
+                    self.bolt_diameter / 2.0,
+                )
+            })
+            .collect()
+    }
+}
+
+/// Metric screw with a hex nut for the T-slot joints
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub enum MetricScrew {
+    #[default]
+    M3,
+    M4,
+    M5,
+}
+
+impl MetricScrew {
+    /// Clearance hole for the bolt, nut width across flats, nut thickness and the bolt
+    /// length used, all in mm
+    pub fn sizes(&self) -> (f32, f32, f32, f32) {
+        match self {
+            MetricScrew::M3 => (3.4, 5.5, 2.4, 16.0),
+            MetricScrew::M4 => (4.5, 7.0, 3.2, 20.0),
+            MetricScrew::M5 => (5.5, 8.0, 4.0, 25.0),
+        }
+    }
+
+    /// T-slot joint for this screw with the bolts between the taps
+    pub fn t_slot(&self) -> TSlot {
+        let (bolt_diameter, nut_width, nut_thickness, bolt_length) = self.sizes();
+        TSlot {
+            tabs: StraightTabs::default(),
+            bolt_diameter,
+            bolt_length,
+            nut_width,
+            nut_thickness,
+        }
+    }
+}
+
+impl FromStr for MetricScrew {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "m3" => Ok(MetricScrew::M3),
+            "m4" => Ok(MetricScrew::M4),
+            "m5" => Ok(MetricScrew::M5),
+            _ => Err(format!("Unknown screw '{}', use m3, m4 or m5", value)),
+        }
+    }
+}
+
+impl fmt::Display for MetricScrew {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetricScrew::M3 => write!(f, "m3"),
+            MetricScrew::M4 => write!(f, "m4"),
+            MetricScrew::M5 => write!(f, "m5"),
+        }
+    }
+}
+
+impl TryFrom<String> for MetricScrew {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<MetricScrew> for String {
+    fn from(screw: MetricScrew) -> Self {
+        screw.to_string()
+    }
+}
+
+/// Tabs split in the middle with barbs at the tip. Halves squeeze together on the way through
+/// the notch and the barbs hold the female part
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapFit {
+    pub tabs: StraightTabs,
+    /// How far the barb sticks out on both sides of the tab
+    pub barb: f32,
+    /// Width of the slit between the halves of the tab
+    pub slit_width: f32,
+}
+
+impl Default for SnapFit {
+    fn default() -> Self {
+        SnapFit {
+            tabs: StraightTabs::default(),
+            barb: 1.0,
+            slit_width: 2.0,
+        }
+    }
+}
+
+impl JointStyle for SnapFit {
+    fn tabs(&self, length: f32, material_thickness: f32) -> Vec<(f32, f32)> {
+        self.tabs.tabs(length, material_thickness)
+    }
+
+    fn male(&self, length: f32, material_thickness: f32) -> Vec<Point> {
+        let base = -material_thickness;
+        // Barbs catch behind the female part and taper to the tip
+        let tip = material_thickness;
+        let slit_bottom = base - 2.0 * material_thickness;
+        let slit = self.slit_width / 2.0;
+
+        let mut profile = vec![(0.0, base)];
+        for (from, to) in self.tabs(length, material_thickness) {
+            let center = (from + to) / 2.0;
+            profile.extend([
+                (from, base),
+                (from, 0.0),
+                (from - self.barb, 0.0),
+                (from, tip),
+                (center - slit, tip),
+                (center - slit, slit_bottom),
+                (center + slit, slit_bottom),
+                (center + slit, tip),
+                (to, tip),
+                (to + self.barb, 0.0),
+                (to, 0.0),
+                (to, base),
+            ]);
+        }
+        profile.push((length, base));
+        profile
+    }
+}
+
+/// Circle as a polygon, about 15 degrees between the points
+fn circle(center: Point, radius: f32) -> Vec<Point> {
+    (0..24)
+        .map(|step| {
+            let angle = step as f32 * PI / 12.0;
+            (
+                center.0 + radius * angle.cos(),
+                center.1 + radius * angle.sin(),
+            )
+        })
+        .collect()
+}
+
+/// Built-in joint between the side panels and the covers
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub enum CornerJoint {
+    /// Two taps near the front and the back
+    #[default]
+    Taps,
+    /// Evenly spaced fingers along the full depth
+    Fingers,
+    /// Two taps with split tips that snap into the covers
+    SnapFit,
+    /// Two taps and a screw between them, nut is held in a pocket in the side panel
+    TSlot,
+    /// Fingers wider at the tip, the tips show on the outer faces of the covers
+    Dovetail,
+}
+
+impl CornerJoint {
+    /// Joint style drawing this joint, `screw` is used by the T-slot joint
+    pub fn style(&self, screw: MetricScrew) -> Box<dyn JointStyle> {
+        match self {
+            CornerJoint::Taps => Box::new(StraightTabs::default()),
+            CornerJoint::Fingers => Box::new(FingerJoint),
+            CornerJoint::SnapFit => Box::new(SnapFit::default()),
+            CornerJoint::TSlot => Box::new(screw.t_slot()),
+            CornerJoint::Dovetail => Box::new(Dovetail::default()),
+        }
+    }
+
+    /// Start and end of every tap of the side panel, measured from the front of the rack
+    pub fn taps(&self, depth: usize, material_thickness: f32) -> Vec<(f32, f32)> {
+        self.style(MetricScrew::default())
+            .tabs(depth as f32, material_thickness)
+    }
+}
+
+impl FromStr for CornerJoint {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "taps" => Ok(CornerJoint::Taps),
+            "fingers" => Ok(CornerJoint::Fingers),
+            "snap-fit" | "snapfit" => Ok(CornerJoint::SnapFit),
+            "t-slot" | "tslot" => Ok(CornerJoint::TSlot),
+            "dovetail" => Ok(CornerJoint::Dovetail),
+            _ => Err(format!(
+                "Unknown joint '{}', use taps, fingers, snap-fit, t-slot or dovetail",
+                value
+            )),
+        }
+    }
+}
+
+impl fmt::Display for CornerJoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CornerJoint::Taps => write!(f, "taps"),
+            CornerJoint::Fingers => write!(f, "fingers"),
+            CornerJoint::SnapFit => write!(f, "snap-fit"),
+            CornerJoint::TSlot => write!(f, "t-slot"),
+            CornerJoint::Dovetail => write!(f, "dovetail"),
+        }
+    }
+}
+
+impl TryFrom<String> for CornerJoint {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<CornerJoint> for String {
+    fn from(joint: CornerJoint) -> Self {
+        joint.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wing_tabs_are_two_at_each_end() {
+        assert_eq!(
+            WingTabs::default().tabs(210.0, 4.0),
+            vec![(20.0, 40.0), (55.0, 75.0), (135.0, 155.0), (170.0, 190.0)]
+        );
+    }
+
+    #[test]
+    fn slots_follow_the_tabs() {
+        let slots = StraightTabs::default().slots(210.0, 4.2);
+        assert_eq!(
+            slots[0],
+            vec![(30.0, 0.0), (60.0, 0.0), (60.0, -4.2), (30.0, -4.2)]
+        );
+        assert_eq!(slots.len(), 2);
+    }
+
+    #[test]
+    fn corner_joint_parses_and_round_trips() {
+        for joint in ["taps", "fingers", "snap-fit", "t-slot", "dovetail"] {
+            assert_eq!(joint.parse::<CornerJoint>().unwrap().to_string(), joint);
+        }
+        assert!("mortise".parse::<CornerJoint>().is_err());
+    }
+
+    #[test]
+    fn finger_count_is_odd_and_fingers_are_as_wide_as_the_material() {
+        for thickness in [3.0, 4.0, 6.0, 9.0, 12.0] {
+            for depth in (3 * thickness as usize..=600).step_by(7) {
+                let count = finger_count(depth, thickness);
+                assert_eq!(count % 2, 1, "{} fingers along {}mm", count, depth);
+                assert!(depth as f32 / count as f32 >= thickness);
+            }
+        }
+    }
+
+    #[test]
+    fn finger_joint_profiles_line_up() {
+        let (length, thickness) = (210.0, 4.0);
+        let count = finger_count(210, thickness);
+        let width = length / count as f32;
+
+        // Tabs of the male part are every other finger, starting with the second one
+        let tabs = FingerJoint.tabs(length, thickness);
+        assert_eq!(tabs.len(), count / 2);
+        for (index, (from, to)) in tabs.iter().enumerate() {
+            assert!((from - (2 * index + 1) as f32 * width).abs() < 0.001);
+            assert!((to - from - width).abs() < 0.001);
+        }
+
+        // Notches of the female part are where the tabs are, as deep as they are tall
+        let male = FingerJoint.male(length, thickness);
+        let female = FingerJoint.female(length, thickness);
+        let flipped = male
+            .iter()
+            .map(|(along, out)| (*along, -thickness - out))
+            .collect::<Vec<Point>>();
+        assert_eq!(female, flipped);
+    }
+
+    #[test]
+    fn default_screw_fits_4mm_material() {
+        assert!(MetricScrew::default().sizes().0 < 4.0);
+    }
+
+    #[test]
+    fn t_slot_pocket_holds_the_nut_at_the_end_of_the_bolt() {
+        let t_slot = MetricScrew::M3.t_slot();
+        let male = t_slot.male(210.0, 4.0);
+        let bolts = t_slot.bolts(210.0, 4.0);
+        assert!(!bolts.is_empty());
+
+        for center in bolts {
+            let start = male
+                .iter()
+                .position(|(x, y)| (x - (center - 1.7)).abs() < 0.001 && *y == -4.0)
+                .unwrap();
+            // 16mm bolt through the 4mm cover ends on the far side of the 2.4mm nut
+            let expected = [
+                (-1.7, -4.0),
+                (-1.7, -13.6),
+                (-2.75, -13.6),
+                (-2.75, -16.0),
+                (2.75, -16.0),
+                (2.75, -13.6),
+                (1.7, -13.6),
+                (1.7, -4.0),
+            ];
+            for ((x, y), (dx, expected_y)) in male[start..start + 8].iter().zip(expected) {
+                assert!((x - (center + dx)).abs() < 0.001);
+                assert!((y - expected_y).abs() < 0.001);
+            }
+        }
+    }
+
+    #[test]
+    fn t_slot_bolt_holes_are_in_the_middle_of_the_cover() {
+        let t_slot = MetricScrew::M4.t_slot();
+        let holes = t_slot.female_holes(210.0, 6.0);
+        assert_eq!(holes.len(), t_slot.bolts(210.0, 6.0).len());
+
+        for (hole, center) in holes.iter().zip(t_slot.bolts(210.0, 6.0)) {
+            let (min_x, min_y, max_x, max_y) = crate::rack::geometry::bounding_box(hole);
+            assert!((max_x - min_x - 4.5).abs() < 0.001);
+            assert!((max_y - min_y - 4.5).abs() < 0.05);
+            assert!(((min_x + max_x) / 2.0 - center).abs() < 0.001);
+            assert!(((min_y + max_y) / 2.0 + 3.0).abs() < 0.001);
+        }
+    }
+
+    #[test]
+    fn dovetail_tails_are_wider_at_the_tip() {
+        let dovetail = Dovetail::default();
+        let spread = dovetail.spread(4.0);
+        assert!((spread - 4.0 * 10.0_f32.to_radians().tan()).abs() < 1e-5);
+
+        // First tail of 210mm with 4mm material, fingers are 210 / 9 wide
+        let width = 210.0 / 9.0;
+        let male = dovetail.male(210.0, 4.0);
+        assert_eq!(male[0], (0.0, -4.0));
+        assert_eq!(male[1], (width + spread, -4.0));
+        assert_eq!(male[2], (width - spread, 0.0));
+        assert_eq!(male[3], (2.0 * width + spread, 0.0));
+        assert_eq!(male[4], (2.0 * width - spread, -4.0));
+        assert_eq!(*male.last().unwrap(), (210.0, -4.0));
+    }
+
+    #[test]
+    fn dovetail_female_takes_the_tips() {
+        let dovetail = Dovetail::default();
+        let spread = dovetail.spread(4.0);
+        let width = 210.0 / 9.0;
+        let female = dovetail.female(210.0, 4.0);
+        assert_eq!(
+            female[1..5],
+            [
+                (width - spread, 0.0),
+                (width - spread, -4.0),
+                (2.0 * width + spread, -4.0),
+                (2.0 * width + spread, 0.0)
+            ]
+        );
+        assert_eq!(dovetail.slots(210.0, 4.0).len(), 4);
+
+        // In the same plane the notches are the tails moved into the other part
+        let male = dovetail.male(210.0, 4.0);
+        let in_plane = dovetail.in_plane_female(210.0, 4.0);
+        assert_eq!(male.len(), in_plane.len());
+        assert!(male
+            .iter()
+            .zip(&in_plane)
+            .all(|(tail, notch)| *notch == (tail.0, -4.0 - tail.1)));
+    }
+}
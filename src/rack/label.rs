@@ -0,0 +1,140 @@
+//! Label holders for the slots of the rack.
+//!
+//! Every labelled slot gets a small holder with its label engraved on it. Holder has a slot
+//! at its left end and hangs on a tab on the front edge of the side panel left of the slot.
+
+use std::collections::BTreeMap;
+
+use svg::node::element::{Path, Text};
+use svg::{Document, Node};
+
+use super::{generate_hole_path, polyline_data, CLEARANCE_BETWEEN_PATHS};
+
+pub const LABEL_HOLDER_WIDTH: usize = 40;
+pub const LABEL_HOLDER_HEIGHT: usize = 15;
+/// Length of the tab on the side panel, along the height of the rack
+pub const LABEL_TAB_WIDTH: usize = 8;
+const LABEL_SLOT_FROM_EDGE: f32 = 3.0;
+const LABEL_TEXT_SIZE: f32 = 5.0;
+
+/// Label text of the slots by row and column, both counted from 0
+pub type Labels = BTreeMap<(usize, usize), String>;
+
+/// Height needed on the sheet for the holders when they are laid out in lines of `width`
+pub fn holders_height(count: usize, width: f32) -> f32 {
+    if count == 0 {
+        return 0.0;
+    }
+    let lines = count.div_ceil(holders_per_line(width));
+    (lines * (LABEL_HOLDER_HEIGHT + CLEARANCE_BETWEEN_PATHS)) as f32
+}
+
+fn holders_per_line(width: f32) -> usize {
+    let pitch = (LABEL_HOLDER_WIDTH + CLEARANCE_BETWEEN_PATHS) as f32;
+    (((width + CLEARANCE_BETWEEN_PATHS as f32) / pitch) as usize).max(1)
+}
+
+/// Holders for the labels in lines of `width`, slot is `slot_width` wide for the panel tab
+pub fn generate_label_holders<'a>(
+    document: &mut Document,
+    starting_point_x: f32,
+    starting_point_y: f32,
+    width: f32,
+    labels: impl IntoIterator<Item = &'a String>,
+    slot_width: f32,
+    colors: (&str, &str, &str),
+) {
+    let (primary_color, secondary_color, engrave_color) = colors;
+    let (holder_width, holder_height) = (LABEL_HOLDER_WIDTH as f32, LABEL_HOLDER_HEIGHT as f32);
+    let per_line = holders_per_line(width);
+
+    for (index, text) in labels.into_iter().enumerate() {
+        let x = starting_point_x
+            + ((index % per_line) * (LABEL_HOLDER_WIDTH + CLEARANCE_BETWEEN_PATHS)) as f32;
+        let y = starting_point_y
+            + ((index / per_line) * (LABEL_HOLDER_HEIGHT + CLEARANCE_BETWEEN_PATHS)) as f32;
+
+        let slot_x = x + LABEL_SLOT_FROM_EDGE;
+        let slot_y = y + (holder_height - LABEL_TAB_WIDTH as f32) / 2.0;
+        document.append(generate_hole_path(
+            &[
+                (slot_x, slot_y),
+                (slot_x, slot_y + LABEL_TAB_WIDTH as f32),
+                (slot_x + slot_width, slot_y + LABEL_TAB_WIDTH as f32),
+                (slot_x + slot_width, slot_y),
+            ],
+            primary_color,
+        ));
+        document.append(
+            Path::new()
+                .set("fill", "none")
+                .set("stroke", secondary_color)
+                .set(
+                    "d",
+                    polyline_data(
+                        &[
+                            (x, y),
+                            (x, y + holder_height),
+                            (x + holder_width, y + holder_height),
+                            (x + holder_width, y),
+                        ],
+                        true,
+                    ),
+                ),
+        );
+
+        // Text is centred in the part right of the slot
+        let text_left = slot_x + slot_width;
+        document.append(
+            Text::new(text.as_str())
+                .set("x", (text_left + x + holder_width) / 2.0)
+                .set("y", y + (holder_height + LABEL_TEXT_SIZE) / 2.0 - 1.0)
+                .set("fill", engrave_color)
+                .set("font-family", "sans-serif")
+                .set("font-size", LABEL_TEXT_SIZE)
+                .set("text-anchor", "middle"),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rack::geometry::document_parts;
+
+    #[test]
+    fn holders_wrap_to_the_next_line() {
+        let line = (LABEL_HOLDER_HEIGHT + CLEARANCE_BETWEEN_PATHS) as f32;
+        assert_eq!(holders_height(0, 100.0), 0.0);
+        assert_eq!(holders_height(2, 100.0), line);
+        assert_eq!(holders_height(3, 100.0), 2.0 * line);
+        assert_eq!(holders_height(3, 10.0), 3.0 * line);
+    }
+
+    #[test]
+    fn holder_has_slot_for_the_tab_and_its_label() {
+        let mut document = Document::new();
+        let labels = ["Screws".to_string(), "Nuts".to_string()];
+        generate_label_holders(
+            &mut document,
+            0.0,
+            0.0,
+            100.0,
+            &labels,
+            4.3,
+            ("black", "blue", "red"),
+        );
+
+        let parts = document_parts(&document);
+        assert_eq!(parts.len(), 2);
+        for part in parts {
+            let (min_x, min_y, max_x, max_y) = part.bounding_box();
+            assert_eq!((max_x - min_x, max_y - min_y), (40.0, 15.0));
+            assert_eq!(part.holes.len(), 1);
+            let (x0, y0, x1, y1) = crate::rack::geometry::bounding_box(&part.holes[0].points);
+            assert!((x1 - x0 - 4.3).abs() < 0.001);
+            assert_eq!(y1 - y0, LABEL_TAB_WIDTH as f32);
+            assert_eq!(part.engravings.len(), 1);
+        }
+    }
+}
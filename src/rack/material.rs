@@ -0,0 +1,146 @@
+//! Sheet materials: real thickness of the sheet, laser kerf and how tight the joints are.
+
+use std::fmt;
+
+/// Sheet material the rack is cut from, all measurements in mm
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct Material {
+    /// Name used to select the preset, e.g. `birch-4mm`
+    pub name: String,
+    pub description: String,
+    /// Thickness the material is sold as
+    pub nominal_thickness: f32,
+    /// Thickness the sheet really has, used for all geometry
+    pub measured_thickness: f32,
+    /// Width of the cut, outlines are moved out and holes in by half of it
+    pub kerf: f32,
+    /// Extra width of the slots, so tabs slide in without force
+    pub fit_clearance: f32,
+    /// Price of one square meter of the sheet, used for cost estimates
+    pub price_per_square_meter: f32,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Material::from(0.0)
+    }
+}
+
+/// Material with exact thickness, no kerf and no clearance
+impl From<f32> for Material {
+    fn from(thickness: f32) -> Self {
+        Material {
+            name: format!("{}mm", thickness),
+            description: String::new(),
+            nominal_thickness: thickness,
+            measured_thickness: thickness,
+            kerf: 0.0,
+            fit_clearance: 0.0,
+            price_per_square_meter: 0.0,
+        }
+    }
+}
+
+impl From<&Material> for Material {
+    fn from(material: &Material) -> Self {
+        material.clone()
+    }
+}
+
+impl Material {
+    /// Width of the slot a tab of this material goes into
+    pub fn slot_width(&self) -> f32 {
+        self.measured_thickness + self.fit_clearance
+    }
+}
+
+impl fmt::Display for Material {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}mm, measured {}mm)",
+            self.name, self.nominal_thickness, self.measured_thickness
+        )
+    }
+}
+
+/// Built-in materials with typical measurements, measure your own sheets for best fit
+pub fn material_presets() -> Vec<Material> {
+    vec![
+        preset("birch-3mm", "Birch plywood", 3.0, 2.8, 0.15, 0.1),
+        preset("birch-4mm", "Birch plywood", 4.0, 3.6, 0.2, 0.1),
+        preset("birch-6mm", "Birch plywood", 6.0, 5.6, 0.25, 0.1),
+        preset("poplar-3mm", "Poplar plywood", 3.0, 2.7, 0.15, 0.1),
+        preset("mdf-3mm", "MDF", 3.0, 2.9, 0.2, 0.05),
+        preset("mdf-6mm", "MDF", 6.0, 5.9, 0.25, 0.05),
+        preset("acrylic-3mm", "Cast acrylic", 3.0, 2.9, 0.15, 0.15),
+        preset("acrylic-5mm", "Cast acrylic", 5.0, 4.8, 0.2, 0.15),
+    ]
+}
+
+fn preset(
+    name: &str,
+    description: &str,
+    nominal_thickness: f32,
+    measured_thickness: f32,
+    kerf: f32,
+    fit_clearance: f32,
+) -> Material {
+    Material {
+        name: name.to_string(),
+        description: description.to_string(),
+        nominal_thickness,
+        measured_thickness,
+        kerf,
+        fit_clearance,
+        price_per_square_meter: 0.0,
+    }
+}
+
+/// Add user materials to presets, user material replaces preset with the same name
+pub fn merge_materials(presets: Vec<Material>, user_materials: Vec<Material>) -> Vec<Material> {
+    let mut materials = presets;
+    for material in user_materials {
+        match materials.iter_mut().find(|m| m.name == material.name) {
+            Some(existing) => *existing = material,
+            None => materials.push(material),
+        }
+    }
+    materials
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_material_replaces_preset_with_same_name() {
+        let presets = material_presets();
+        let birch = Material {
+            measured_thickness: 3.8,
+            ..presets[1].clone()
+        };
+        let oak = Material::from(5.0);
+
+        let materials = merge_materials(presets.clone(), vec![birch.clone(), oak.clone()]);
+
+        assert_eq!(materials.len(), presets.len() + 1);
+        assert_eq!(materials[1], birch);
+        assert_eq!(materials.last(), Some(&oak));
+    }
+
+    #[test]
+    fn slot_is_measured_thickness_with_clearance() {
+        let material = material_presets()
+            .into_iter()
+            .find(|material| material.name == "birch-4mm")
+            .unwrap();
+        assert!((material.slot_width() - 3.7).abs() < 0.001);
+        assert_eq!(Material::from(4.0).slot_width(), 4.0);
+    }
+}
@@ -1,8 +1,39 @@
+#![allow(clippy::too_many_arguments)]
+
+use std::sync::Arc;
+use std::{fmt, io};
 use svg::node::element::path::Data;
 use svg::node::element::Path;
 use svg::{Document, Node};
 use url::Url;
 
+use geometry::Point;
+use joint::{CornerJoint, JointStyle, MetricScrew, WingTabs};
+use label::Labels;
+use material::Material;
+use mount::WallMount;
+use relief::ReliefStyle;
+use retention::RetentionBars;
+use stop::DrawerStop;
+
+pub mod bom;
+pub mod calibration;
+pub mod dxf;
+pub mod estimate;
+pub mod gcode;
+pub mod geometry;
+pub mod joint;
+pub mod label;
+pub mod material;
+pub mod mount;
+pub mod nesting;
+pub mod relief;
+pub mod retention;
+pub mod spec;
+pub mod stats;
+pub mod stop;
+pub mod toolpath;
+
 // All measurements are in mm
 const SIDE_WING_SLOT_FROM_FRONT: usize = 20;
 const SIDE_WING_SLOT_WIDTH: usize = 20;
@@ -11,12 +42,23 @@ const CLEARANCE_BETWEEN_PATHS: usize = 3;
 const SIDE_TAP_FROM_FRONT: usize = 30;
 const SIDE_TAP_WIDTH: usize = 30;
 const CLEARANCE_FOR_CONTAINER_WIDTH: usize = 4;
+const BACK_STRETCHER_HEIGHT: usize = 60;
+const BACK_STRETCHER_TAB_WIDTH: usize = 20;
+const STACKING_PEG_WIDTH: usize = 10;
+/// Limits of the layout, well above any rack that fits in a room
+pub const MAX_ROWS: usize = 100;
+pub const MAX_COLUMNS: usize = 100;
+/// Largest row height and column width in mm
+pub const MAX_SIZE: usize = 3000;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Container {
     pub vendor: String,
     pub model: String,
     pub description: String,
+    /// Containers in the same group can share a rack
+    pub compatibility_group: String,
     pub links: Vec<ContainerLink>,
     pub dimensions: Dimensions,
 }
@@ -27,9 +69,19 @@ impl Container {
             .to_lowercase()
             .replace(" ", "_")
     }
+
+    pub fn footprint(&self) -> Footprint {
+        self.dimensions.footprint()
+    }
+
+    /// Check that both containers can hang in the same rack
+    pub fn check_compatible(&self, other: &Container) -> Result<(), FootprintMismatch> {
+        self.footprint().check_compatible(&other.footprint())
+    }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Dimensions {
     pub width: usize,
     pub depth: usize,
@@ -38,36 +90,613 @@ pub struct Dimensions {
     pub side_wing_width: usize,
 }
 
+impl Dimensions {
+    pub fn footprint(&self) -> Footprint {
+        Footprint {
+            width: self.width,
+            depth: self.depth,
+            side_wing_from_box_top: self.side_wing_from_box_top,
+        }
+    }
+}
+
+/// Part of the dimensions that must match for containers to share a rack.
+///
+/// Containers with the same footprint differ only in height, so they can be mixed in rows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Footprint {
+    pub width: usize,
+    pub depth: usize,
+    pub side_wing_from_box_top: usize,
+}
+
+impl Footprint {
+    pub fn check_compatible(&self, other: &Footprint) -> Result<(), FootprintMismatch> {
+        [
+            ("width", self.width, other.width),
+            ("depth", self.depth, other.depth),
+            (
+                "side_wing_from_box_top",
+                self.side_wing_from_box_top,
+                other.side_wing_from_box_top,
+            ),
+        ]
+        .into_iter()
+        .find(|(_, expected, found)| expected != found)
+        .map_or(Ok(()), |(dimension, expected, found)| {
+            Err(FootprintMismatch {
+                dimension,
+                expected,
+                found,
+            })
+        })
+    }
+}
+
+/// First dimension that differs between two footprints
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FootprintMismatch {
+    pub dimension: &'static str,
+    pub expected: usize,
+    pub found: usize,
+}
+
+impl fmt::Display for FootprintMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is {}mm but {}mm is expected",
+            self.dimension, self.found, self.expected
+        )
+    }
+}
+
+impl std::error::Error for FootprintMismatch {}
+
+/// Reason why layout can't be generated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayoutError {
+    NoRows,
+    NoColumns,
+    ZeroWidthColumn {
+        column: usize,
+    },
+    /// Row, counted from 1, doesn't share footprint with the first row
+    IncompatibleRow {
+        row: usize,
+        mismatch: FootprintMismatch,
+    },
+    TooManyRows(usize),
+    TooManyColumns(usize),
+    /// Row, counted from 1, is taller than `MAX_SIZE`
+    RowTooTall {
+        row: usize,
+        height: usize,
+    },
+    /// Row, counted from 1, is lower than its side wing with the gap above it
+    RowTooShort {
+        row: usize,
+        height: usize,
+        min_height: usize,
+    },
+    /// Column, counted from 1, is wider than `MAX_SIZE`
+    ColumnTooWide {
+        column: usize,
+        width: usize,
+    },
+    InvalidDrawerStopDepth,
+    /// Notch of the drawer stop takes more than half of the side wing in the row, counted from 1
+    DrawerStopTooDeep {
+        row: usize,
+        side_wing_width: usize,
+    },
+    /// Tab of the drawer stop reaches over the top of the row, counted from 1
+    DrawerStopTooTall {
+        row: usize,
+        side_wing_from_box_top: usize,
+    },
+    /// Slot for the tab of the drawer stop leaves less than a quarter of the side wing on
+    /// each side in the row, counted from 1
+    DrawerStopSlotTooWide {
+        row: usize,
+        side_wing_width: usize,
+    },
+    /// Bolt hole of the T-slot joint is as wide as the material or wider, it breaks out of
+    /// the edges of the covers
+    BoltHoleTooWide(MetricScrew),
+    /// No relief for the router bit fits on the edges of this many corners of the slots
+    ReliefDoesNotFit {
+        corners: usize,
+    },
+    /// Keyhole of the side panel wall mount is longer than the first row is high
+    KeyholeDoesNotFit {
+        row_height: usize,
+    },
+    /// Router bit is as wide as this many holes or wider, it would cut past their walls
+    ToolDoesNotFit {
+        holes: usize,
+    },
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutError::NoRows => write!(f, "Rack needs at least one row"),
+            LayoutError::NoColumns => write!(f, "Rack needs at least one column"),
+            LayoutError::ZeroWidthColumn { column } => {
+                write!(f, "Column {} has no width", column)
+            }
+            LayoutError::IncompatibleRow { row, mismatch } => write!(
+                f,
+                "Container in row {} can't share the rack with the container in row 1: {}",
+                row, mismatch
+            ),
+            LayoutError::TooManyRows(rows) => {
+                write!(f, "Rack can have at most {} rows, got {}", MAX_ROWS, rows)
+            }
+            LayoutError::TooManyColumns(columns) => write!(
+                f,
+                "Rack can have at most {} columns, got {}",
+                MAX_COLUMNS, columns
+            ),
+            LayoutError::RowTooTall { row, height } => write!(
+                f,
+                "Row {} is {}mm tall, rows can be at most {}mm",
+                row, height, MAX_SIZE
+            ),
+            LayoutError::RowTooShort {
+                row,
+                height,
+                min_height,
+            } => write!(
+                f,
+                "Row {} is {}mm tall, it needs at least {}mm for the side wing and the gap above it",
+                row, height, min_height
+            ),
+            LayoutError::ColumnTooWide { column, width } => write!(
+                f,
+                "Column {} is {}mm wide, columns can be at most {}mm",
+                column, width, MAX_SIZE
+            ),
+            LayoutError::InvalidDrawerStopDepth => {
+                write!(f, "Drawer stop depth must be positive")
+            }
+            LayoutError::DrawerStopTooDeep {
+                row,
+                side_wing_width,
+            } => write!(
+                f,
+                "Drawer stop notch must be shallower than half of the {}mm side wing in row {}",
+                side_wing_width, row
+            ),
+            LayoutError::DrawerStopTooTall {
+                row,
+                side_wing_from_box_top,
+            } => write!(
+                f,
+                "Drawer stop tab must be at most {}mm tall to stay below the top of row {}",
+                side_wing_from_box_top, row
+            ),
+            LayoutError::DrawerStopSlotTooWide {
+                row,
+                side_wing_width,
+            } => write!(
+                f,
+                "Slot of the drawer stop doesn't fit the {}mm side wing in row {}, use material \
+                 at most half as thick as the wing",
+                side_wing_width, row
+            ),
+            LayoutError::BoltHoleTooWide(screw) => write!(
+                f,
+                "{}mm bolt hole of {} screw breaks out of the covers, use a smaller screw or \
+                 material thicker than the hole",
+                screw.sizes().0,
+                screw.to_string().to_uppercase()
+            ),
+            LayoutError::ReliefDoesNotFit { corners } => write!(
+                f,
+                "Reliefs don't fit in {} corners of the slots, use a thinner router bit",
+                corners
+            ),
+            LayoutError::KeyholeDoesNotFit { row_height } => write!(
+                f,
+                "Keyhole of the wall mount doesn't fit in the {}mm first row, use a taller \
+                 first row or the back stretcher",
+                row_height
+            ),
+            LayoutError::ToolDoesNotFit { holes } => write!(
+                f,
+                "Router bit is as wide as {} holes or wider, use a thinner router bit",
+                holes
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ContainerLink {
     pub url: Url,
     pub title: String,
 }
+/// Optional features of the generated parts, all off by default
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct RackOptions {
+    /// Side wings are stacked so neighbouring wings share one cut line
+    pub common_line: bool,
+    /// Relief at the corners of the slots for cutting with a router bit
+    pub relief: Option<ReliefStyle>,
+    /// Diameter of the router bit in mm, sizes the reliefs
+    pub tool_diameter: f32,
+    /// Joint between the side panels and the covers
+    pub corner_joint: CornerJoint,
+    /// Screw of the T-slot joints
+    pub screw: MetricScrew,
+    /// Part with keyhole slots for screwing the rack to a wall
+    pub wall_mount: Option<WallMount>,
+    /// Holes in the covers and pegs for them, so stacked racks with the same columns register
+    pub stacking: bool,
+    /// Color of the engraved text, e.g. labels
+    pub engrave_color: String,
+    /// Stop at the back end of the side wings that keeps the containers in
+    pub drawer_stop: Option<DrawerStop>,
+    /// How far the tab of the drawer stop stands above the wing or the notch cuts into it in mm
+    pub drawer_stop_depth: f32,
+    /// Removable bars across the front of the rows, standing in slots of the side panels
+    pub retention_bars: Option<RetentionBars>,
+}
+
+impl Default for RackOptions {
+    fn default() -> Self {
+        RackOptions {
+            common_line: false,
+            relief: None,
+            tool_diameter: 3.175,
+            corner_joint: CornerJoint::Taps,
+            screw: MetricScrew::default(),
+            wall_mount: None,
+            stacking: false,
+            engrave_color: "red".to_string(),
+            drawer_stop: None,
+            drawer_stop_depth: 3.0,
+            retention_bars: None,
+        }
+    }
+}
+
+/// Rows and columns of the rack
+#[derive(Debug, Clone)]
+pub struct Layout {
+    /// Dimensions of the container in each row, from top to bottom
+    pub rows: Vec<Dimensions>,
+    /// Width of the container in each column, from left to right
+    pub column_widths: Vec<usize>,
+    pub options: RackOptions,
+    /// Own joint between the side panels and the covers, overrides `options.corner_joint`
+    pub corner_joint_style: Option<Arc<dyn JointStyle>>,
+    /// Own joint between the side wings and the side panels, overrides `WingTabs`. Wings of
+    /// a pair take every other tab
+    pub wing_joint_style: Option<Arc<dyn JointStyle>>,
+    /// Slots with a label holder and the text engraved on it
+    pub labels: Labels,
+}
+
+impl Layout {
+    /// Layout where every row and column holds the same container
+    pub fn uniform(dimensions: &Dimensions, rows: usize, columns: usize) -> Layout {
+        Layout::with_rows(vec![dimensions.clone(); rows], columns)
+    }
+
+    /// Layout with given rows and columns as wide as the container in the first row
+    pub fn with_rows(rows: Vec<Dimensions>, columns: usize) -> Layout {
+        let column_widths = vec![rows.first().map(|row| row.width).unwrap_or_default(); columns];
+        Layout {
+            rows,
+            column_widths,
+            options: RackOptions::default(),
+            corner_joint_style: None,
+            wing_joint_style: None,
+            labels: Labels::new(),
+        }
+    }
+
+    /// Check that the layout has rows and columns and all rows share the same footprint
+    pub fn validate(&self) -> Result<(), LayoutError> {
+        let first_row = self.rows.first().ok_or(LayoutError::NoRows)?;
+        if self.column_widths.is_empty() {
+            return Err(LayoutError::NoColumns);
+        }
+        if let Some(column) = self.column_widths.iter().position(|width| *width == 0) {
+            return Err(LayoutError::ZeroWidthColumn { column: column + 1 });
+        }
+        if self.rows.len() > MAX_ROWS {
+            return Err(LayoutError::TooManyRows(self.rows.len()));
+        }
+        if self.column_widths.len() > MAX_COLUMNS {
+            return Err(LayoutError::TooManyColumns(self.column_widths.len()));
+        }
+        if let Some(row) = self.rows.iter().position(|row| row.height > MAX_SIZE) {
+            return Err(LayoutError::RowTooTall {
+                row: row + 1,
+                height: self.rows[row].height,
+            });
+        }
+        if let Some(column) = self
+            .column_widths
+            .iter()
+            .position(|width| *width > MAX_SIZE)
+        {
+            return Err(LayoutError::ColumnTooWide {
+                column: column + 1,
+                width: self.column_widths[column],
+            });
+        }
+
+        let footprint = first_row.footprint();
+        self.rows
+            .iter()
+            .enumerate()
+            .skip(1)
+            .try_for_each(|(index, row)| {
+                footprint
+                    .check_compatible(&row.footprint())
+                    .map_err(|mismatch| LayoutError::IncompatibleRow {
+                        row: index + 1,
+                        mismatch,
+                    })
+            })?;
+
+        if let Some(stop) = self.options.drawer_stop {
+            let depth = self.options.drawer_stop_depth;
+            if depth <= 0.0 {
+                return Err(LayoutError::InvalidDrawerStopDepth);
+            }
+            // Notch must leave half of the wing, tab must not reach the container above
+            let error = self
+                .rows
+                .iter()
+                .enumerate()
+                .find_map(|(index, row)| match stop {
+                    DrawerStop::Notch if 2.0 * depth > row.side_wing_width as f32 => {
+                        Some(LayoutError::DrawerStopTooDeep {
+                            row: index + 1,
+                            side_wing_width: row.side_wing_width,
+                        })
+                    }
+                    DrawerStop::Tab if depth > row.side_wing_from_box_top as f32 => {
+                        Some(LayoutError::DrawerStopTooTall {
+                            row: index + 1,
+                            side_wing_from_box_top: row.side_wing_from_box_top,
+                        })
+                    }
+                    _ => None,
+                });
+            if let Some(error) = error {
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that the parts can be cut out of material of this thickness
+    pub fn validate_material(&self, material_thickness: f32) -> Result<(), LayoutError> {
+        // Wing hangs below the gap from the top of the row, it must not reach the row below
+        let min_height = |row: &Dimensions| row.side_wing_from_box_top as f32 + material_thickness;
+        if let Some(index) = self
+            .rows
+            .iter()
+            .position(|row| (row.height as f32) < min_height(row))
+        {
+            return Err(LayoutError::RowTooShort {
+                row: index + 1,
+                height: self.rows[index].height,
+                min_height: min_height(&self.rows[index]).ceil() as usize,
+            });
+        }
+        // Own joint may have any holes, only the built-in T-slot is known
+        let screw = self.options.screw;
+        if self.corner_joint_style.is_none()
+            && self.options.corner_joint == CornerJoint::TSlot
+            && screw.sizes().0 >= material_thickness
+        {
+            return Err(LayoutError::BoltHoleTooWide(screw));
+        }
+        if self.options.drawer_stop == Some(DrawerStop::Tab) {
+            if let Some(index) = self
+                .rows
+                .iter()
+                .position(|row| 2.0 * material_thickness > row.side_wing_width as f32)
+            {
+                return Err(LayoutError::DrawerStopSlotTooWide {
+                    row: index + 1,
+                    side_wing_width: self.rows[index].side_wing_width,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Joint between the side panels and the covers
+    pub fn corner_joint(&self) -> Arc<dyn JointStyle> {
+        self.corner_joint_style
+            .clone()
+            .unwrap_or_else(|| Arc::from(self.options.corner_joint.style(self.options.screw)))
+    }
+
+    /// Joint between the side wings and the side panels
+    pub fn wing_joint(&self) -> Arc<dyn JointStyle> {
+        self.wing_joint_style
+            .clone()
+            .unwrap_or_else(|| Arc::new(WingTabs::default()))
+    }
+
+    pub fn columns(&self) -> usize {
+        self.column_widths.len()
+    }
+
+    /// Outer width, height and depth of the assembled rack in mm
+    pub fn outer_size(&self, material_thickness: f32) -> (f32, f32, f32) {
+        (
+            top_width(&self.column_pitches(material_thickness), material_thickness),
+            rows_height(&self.rows) as f32 + 2.0 * material_thickness,
+            self.depth() as f32,
+        )
+    }
+
+    /// Depth of the rack, defined by the container in the first row
+    pub fn depth(&self) -> usize {
+        self.rows.first().map_or(0, |row| row.depth)
+    }
+
+    /// Labels of the slots that are in the rack, labels for other slots are left out
+    pub fn slot_labels(&self) -> Labels {
+        self.labels
+            .iter()
+            .filter(|((row, column), _)| {
+                *row < self.rows.len() && *column < self.column_widths.len()
+            })
+            .map(|(slot, text)| (*slot, text.clone()))
+            .collect()
+    }
+
+    /// Distance between side panels for each column, including the panel itself
+    fn column_pitches(&self, material_thickness: f32) -> Vec<f32> {
+        self.column_widths
+            .iter()
+            .map(|width| (width + CLEARANCE_FOR_CONTAINER_WIDTH) as f32 + material_thickness)
+            .collect()
+    }
+}
+
+/// Generate SVG for a rack with rows and columns of the same container, layout is validated
+/// like in `generate_layout_svg`.
 pub fn generate_svg(
     rows: usize,
     columns: usize,
-    material_thickness: f32,
+    material: impl Into<Material>,
     container: &Container,
     primary_color: &str,
     secondary_color: &str,
-) -> Document {
+) -> Result<Document, LayoutError> {
+    generate_layout_svg(
+        &Layout::uniform(&container.dimensions, rows, columns),
+        material,
+        primary_color,
+        secondary_color,
+    )
+}
+
+/// Generate SVG for a rack where every row can hold a container of different height.
+///
+/// Rows are listed from top to bottom. All rows are expected to share the width and depth
+/// of the first row, only height and side wing may vary.
+pub fn generate_svg_with_rows(
+    rows: &[Dimensions],
+    columns: usize,
+    material: impl Into<Material>,
+    primary_color: &str,
+    secondary_color: &str,
+) -> Result<Document, LayoutError> {
+    generate_layout_svg(
+        &Layout::with_rows(rows.to_vec(), columns),
+        material,
+        primary_color,
+        secondary_color,
+    )
+}
+
+/// Generate SVG for a rack with rows of different heights and columns of different widths.
+///
+/// Material can be a preset or just its thickness in mm. Layout is validated first, see
+/// `Layout::validate` and `Layout::validate_material`.
+pub fn generate_layout_svg(
+    layout: &Layout,
+    material: impl Into<Material>,
+    primary_color: &str,
+    secondary_color: &str,
+) -> Result<Document, LayoutError> {
+    layout.validate()?;
+    let material = material.into();
+    let material_thickness = material.measured_thickness;
+    layout.validate_material(material_thickness)?;
+    let fit_clearance = material.fit_clearance;
     let starting_point_x = 0.0;
     let starting_point_y = 0.0;
-    let column_width = container.dimensions.width + CLEARANCE_FOR_CONTAINER_WIDTH;
-    let amount_of_boxes = (rows * columns) as usize;
-    let height_of_two_side_wings =
-        height_of_two_side_wings(container.dimensions.side_wing_width, material_thickness);
-    let height_of_two_side_wings_with_clearance =
-        height_of_two_side_wings + CLEARANCE_BETWEEN_PATHS as f32;
-
-    let total_width = (container.dimensions.depth + (CLEARANCE_BETWEEN_PATHS * 3)) as f32
-        + top_width(column_width as f32, columns, material_thickness)
-        + (container.dimensions.height * rows) as f32
-        + (2.0 * material_thickness);
-    let total_height = vec![
-        amount_of_boxes as f32 * height_of_two_side_wings_with_clearance,
-        (2 * container.dimensions.depth + CLEARANCE_BETWEEN_PATHS) as f32,
-        ((columns + 1) * (container.dimensions.depth + CLEARANCE_BETWEEN_PATHS)) as f32,
+    let rows = &layout.rows;
+    let columns = layout.columns();
+    let depth = layout.depth();
+    let column_pitches = layout.column_pitches(material_thickness);
+    let corner_joint = layout.corner_joint();
+    let wing_joint = layout.wing_joint();
+    // Keyhole must end above the bottom of the first row, where the next wing slot is
+    if layout.options.wall_mount == Some(WallMount::SidePanels) {
+        let keyhole_end = side_panel_keyhole_x(
+            &rows[0],
+            corner_joint.as_ref(),
+            material_thickness,
+            fit_clearance,
+        ) + mount::KEYHOLE_HEAD_DIAMETER / 2.0;
+        if keyhole_end > material_thickness + rows[0].height as f32 {
+            return Err(LayoutError::KeyholeDoesNotFit {
+                row_height: rows[0].height,
+            });
+        }
+    }
+    // Tips of the joint may reach past the ends of the side panels
+    let joint_overhang = corner_joint
+        .male(depth as f32, material_thickness)
+        .iter()
+        .fold(0.0, |overhang: f32, (_, out)| overhang.max(*out));
+    let labels = layout.slot_labels();
+    let front_overhang = front_overhang(&labels, material_thickness);
+    let bar_sizes = layout.options.retention_bars.map_or(vec![], |bars| {
+        retention::bar_sizes(bars, rows, &column_pitches, material_thickness)
+    });
+    let (bar_positions, bars_height) =
+        retention::bar_positions(&bar_sizes, top_width(&column_pitches, material_thickness));
+    // With common line the straight edges of neighbouring wing pairs are one kerf apart,
+    // after kerf compensation they are on the same line
+    let side_wing_pair_gap = if layout.options.common_line {
+        material.kerf
+    } else {
+        CLEARANCE_BETWEEN_PATHS as f32
+    };
+
+    let total_width = (depth + (CLEARANCE_BETWEEN_PATHS * 3)) as f32
+        + top_width(&column_pitches, material_thickness)
+        + rows_height(rows) as f32
+        + (2.0 * material_thickness)
+        + 2.0 * joint_overhang;
+    let stop = layout
+        .options
+        .drawer_stop
+        .map(|stop| (stop, layout.options.drawer_stop_depth));
+    let stop_sizes = match stop {
+        Some((DrawerStop::Tab, depth)) => {
+            vec![stop::piece_size(depth, material_thickness); 2 * columns * rows.len()]
+        }
+        _ => vec![],
+    };
+    let (stop_positions, stops_height) =
+        retention::bar_positions(&stop_sizes, top_width(&column_pitches, material_thickness));
+
+    let total_height = [
+        side_wings_height(rows, columns, material_thickness, side_wing_pair_gap),
+        (2 * depth + CLEARANCE_BETWEEN_PATHS) as f32
+            + extra_parts_height(&column_pitches, material_thickness, &layout.options)
+            + label::holders_height(labels.len(), top_width(&column_pitches, material_thickness))
+            + bars_height
+            + stops_height,
+        (columns + 1) as f32 * ((depth + CLEARANCE_BETWEEN_PATHS) as f32 + front_overhang),
     ]
     .iter()
     .cloned()
@@ -78,169 +707,537 @@ pub fn generate_svg(
         .set("width", format!("{}mm", total_width))
         .set("height", format!("{}mm", total_height));
 
-    // Generate side wings
-    for i in 0..amount_of_boxes {
-        generate_side_wing_pair(
-            &mut document,
-            &container.dimensions,
-            starting_point_x,
-            starting_point_y + height_of_two_side_wings_with_clearance * i as f32,
-            material_thickness,
-            secondary_color,
-        );
+    // Generate side wings, each row gets wings sized for its own container
+    let mut y = starting_point_y;
+    for row in rows {
+        let height_of_two_side_wings_with_clearance =
+            height_of_two_side_wings(row.side_wing_width, material_thickness) + side_wing_pair_gap;
+        for _ in 0..columns {
+            generate_side_wing_pair(
+                &mut document,
+                row,
+                starting_point_x,
+                y,
+                material_thickness,
+                fit_clearance,
+                wing_joint.as_ref(),
+                stop,
+                secondary_color,
+            );
+            y += height_of_two_side_wings_with_clearance;
+        }
     }
 
     // Generate top and bottom pieces
     generate_top_and_bottom_pieces(
         &mut document,
-        &container.dimensions,
-        (container.dimensions.depth + CLEARANCE_BETWEEN_PATHS) as f32,
-        columns,
-        column_width as f32 + material_thickness,
+        &rows[0],
+        (depth + CLEARANCE_BETWEEN_PATHS) as f32,
+        &column_pitches,
         material_thickness,
+        fit_clearance,
+        corner_joint.as_ref(),
+        &layout.options,
         primary_color,
         secondary_color,
     );
 
+    // Back stretcher and stacking pegs go under the covers
+    let mut y = (2 * (depth + CLEARANCE_BETWEEN_PATHS)) as f32;
+    if layout.options.wall_mount == Some(WallMount::BackStretcher) {
+        generate_back_stretcher(
+            &mut document,
+            (depth + CLEARANCE_BETWEEN_PATHS) as f32,
+            y,
+            &column_pitches,
+            material_thickness,
+            primary_color,
+            secondary_color,
+        );
+        y += (BACK_STRETCHER_HEIGHT + CLEARANCE_BETWEEN_PATHS) as f32 + material_thickness;
+    }
+    if layout.options.stacking {
+        generate_stacking_pegs(
+            &mut document,
+            (depth + CLEARANCE_BETWEEN_PATHS) as f32,
+            y,
+            stacking_peg_positions(depth, &column_pitches, material_thickness).len(),
+            material_thickness,
+            secondary_color,
+        );
+        y += CLEARANCE_BETWEEN_PATHS as f32 + 2.0 * material_thickness;
+    }
+    label::generate_label_holders(
+        &mut document,
+        (depth + CLEARANCE_BETWEEN_PATHS) as f32,
+        y,
+        top_width(&column_pitches, material_thickness),
+        labels.values(),
+        material_thickness + fit_clearance,
+        (
+            primary_color,
+            secondary_color,
+            &layout.options.engrave_color,
+        ),
+    );
+    y += label::holders_height(labels.len(), top_width(&column_pitches, material_thickness));
+    for ((length, height), (x, bar_y)) in bar_sizes.iter().zip(&bar_positions) {
+        let (x, bar_y) = ((depth + CLEARANCE_BETWEEN_PATHS) as f32 + x, y + bar_y);
+        document.append(
+            Path::new()
+                .set("fill", "none")
+                .set("stroke", secondary_color)
+                .set(
+                    "d",
+                    polyline_data(
+                        &[
+                            (x, bar_y),
+                            (x, bar_y + height),
+                            (x + length, bar_y + height),
+                            (x + length, bar_y),
+                        ],
+                        true,
+                    ),
+                ),
+        );
+    }
+    y += bars_height;
+    if let Some((_, stop_depth)) = stop {
+        for (x, stop_y) in &stop_positions {
+            let piece = stop::piece(
+                (depth + CLEARANCE_BETWEEN_PATHS) as f32 + x,
+                y + stop_y,
+                stop_depth,
+                material_thickness,
+            );
+            document.append(generate_hole_path(&piece, secondary_color));
+        }
+    }
+
     // generate side panels
     generate_side_panels(
         &mut document,
-        (container.dimensions.depth + CLEARANCE_BETWEEN_PATHS) as f32 //side wings
-            + top_width(column_width as f32, columns, material_thickness) + CLEARANCE_BETWEEN_PATHS as f32,
-        &container.dimensions, // top and bottom plates
-        rows,
+        (depth + CLEARANCE_BETWEEN_PATHS) as f32 //side wings
+            + top_width(&column_pitches, material_thickness) + CLEARANCE_BETWEEN_PATHS as f32
+            + joint_overhang,
+        rows, // top and bottom plates
         columns,
         material_thickness,
+        fit_clearance,
+        corner_joint.as_ref(),
+        wing_joint.as_ref(),
+        &labels,
+        &layout.options,
         primary_color,
         secondary_color,
     );
 
-    document
+    if let Some(style) = layout.options.relief {
+        let tool_diameter = layout.options.tool_diameter;
+        let corners = relief::unrelieved_hole_corners(&document, style, tool_diameter);
+        if corners > 0 {
+            return Err(LayoutError::ReliefDoesNotFit { corners });
+        }
+        document = relief::relieve_holes(&document, style, tool_diameter);
+    }
+    if material.kerf > 0.0 {
+        document = geometry::compensate_kerf(&document, material.kerf);
+    }
+    if layout.options.common_line {
+        document = geometry::remove_common_lines(&document);
+    }
+
+    Ok(document)
+}
+
+/// Render SVG document to a string
+pub fn render_svg(document: &Document) -> String {
+    document.to_string()
+}
+
+/// Write SVG document to any writer, e.g. file, stdout or buffer
+pub fn write_svg<W: io::Write>(writer: W, document: &Document) -> io::Result<()> {
+    svg::write(writer, document)
+}
+
+/// Outline with reliefs in the notches when the rack is cut with a router bit
+fn relieve_outline(data: Data, options: &RackOptions) -> Data {
+    match options.relief {
+        Some(style) => relief::relieve_data(&data, style, options.tool_diameter, false),
+        None => data,
+    }
+}
+
+/// Path data through the points with horizontal and vertical lines where possible
+fn polyline_data(points: &[Point], closed: bool) -> Data {
+    let mut data = match points.first() {
+        Some(first) => Data::new().move_to(*first),
+        None => return Data::new(),
+    };
+    for pair in points.windows(2) {
+        let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+        data = if x1 == x2 && y1 == y2 {
+            data
+        } else if x1 == x2 {
+            data.vertical_line_to(y2)
+        } else if y1 == y2 {
+            data.horizontal_line_to(x2)
+        } else {
+            data.line_to((x2, y2))
+        };
+    }
+    if closed {
+        data.close()
+    } else {
+        data
+    }
+}
+
+fn generate_hole_path(points: &[Point], color: &str) -> Path {
+    Path::new()
+        .set("fill", "none")
+        .set("stroke", color)
+        .set("d", polyline_data(points, true))
+}
+
+/// How far the label tabs stick out of the front of the side panels
+fn front_overhang(labels: &Labels, material_thickness: f32) -> f32 {
+    if labels.is_empty() {
+        0.0
+    } else {
+        material_thickness
+    }
+}
+
+/// Inner height of the side panel, sum of all row heights
+fn rows_height(rows: &[Dimensions]) -> usize {
+    rows.iter().map(|row| row.height).sum()
+}
+
+/// Height needed on the sheet for all side wing pairs, `pair_gap` is the space between pairs
+fn side_wings_height(
+    rows: &[Dimensions],
+    columns: usize,
+    material_thickness: f32,
+    pair_gap: f32,
+) -> f32 {
+    rows.iter()
+        .map(|row| {
+            (height_of_two_side_wings(row.side_wing_width, material_thickness) + pair_gap)
+                * columns as f32
+        })
+        .sum()
+}
+
+/// Height needed on the sheet under the covers for the back stretcher and stacking pegs
+fn extra_parts_height(
+    column_pitches: &[f32],
+    material_thickness: f32,
+    options: &RackOptions,
+) -> f32 {
+    let mut height = 0.0;
+    if options.wall_mount == Some(WallMount::BackStretcher) {
+        height += (CLEARANCE_BETWEEN_PATHS + BACK_STRETCHER_HEIGHT) as f32 + material_thickness;
+    }
+    if options.stacking && !column_pitches.is_empty() {
+        height += CLEARANCE_BETWEEN_PATHS as f32 + 2.0 * material_thickness;
+    }
+    height
+}
+
+/// Middle of every column measured from the left edge of the cover
+fn column_centers(column_pitches: &[f32], material_thickness: f32) -> Vec<f32> {
+    let mut x = material_thickness;
+    column_pitches
+        .iter()
+        .map(|pitch| {
+            let center = x + (pitch - material_thickness) / 2.0;
+            x += pitch;
+            center
+        })
+        .collect()
+}
+
+/// Centers of the stacking holes on the cover, in the first and the last column in line
+/// with the middle of the side taps at the front and the back
+fn stacking_peg_positions(
+    depth: usize,
+    column_pitches: &[f32],
+    material_thickness: f32,
+) -> Vec<Point> {
+    let centers = column_centers(column_pitches, material_thickness);
+    let mut columns = vec![centers[0], centers[centers.len() - 1]];
+    columns.dedup();
+    let from_front = (SIDE_TAP_FROM_FRONT + SIDE_TAP_WIDTH / 2) as f32;
+
+    columns
+        .iter()
+        .flat_map(|x| [(*x, from_front), (*x, depth as f32 - from_front)])
+        .collect()
+}
+
+/// Board standing on the back of the top cover with its tabs in the cover, keyholes in the
+/// middle of every column
+fn generate_back_stretcher(
+    document: &mut Document,
+    starting_point_x: f32,
+    starting_point_y: f32,
+    column_pitches: &[f32],
+    material_thickness: f32,
+    primary_color: &str,
+    secondary_color: &str,
+) {
+    let width = top_width(column_pitches, material_thickness);
+    let height = BACK_STRETCHER_HEIGHT as f32;
+    let centers = column_centers(column_pitches, material_thickness);
+    let half_tab = BACK_STRETCHER_TAB_WIDTH as f32 / 2.0;
+
+    // Tabs on the bottom edge under every column
+    let mut outline = vec![(starting_point_x, starting_point_y)];
+    outline.push((starting_point_x, starting_point_y + height));
+    for center in &centers {
+        let (from, to) = (
+            starting_point_x + center - half_tab,
+            starting_point_x + center + half_tab,
+        );
+        outline.extend([
+            (from, starting_point_y + height),
+            (from, starting_point_y + height + material_thickness),
+            (to, starting_point_y + height + material_thickness),
+            (to, starting_point_y + height),
+        ]);
+    }
+    outline.push((starting_point_x + width, starting_point_y + height));
+    outline.push((starting_point_x + width, starting_point_y));
+
+    // Round part of the keyhole is in the middle, slot goes up
+    for center in &centers {
+        document.append(generate_hole_path(
+            &mount::keyhole(
+                (
+                    starting_point_x + center,
+                    starting_point_y + (height + mount::KEYHOLE_LENGTH) / 2.0,
+                ),
+                (0.0, -1.0),
+            ),
+            primary_color,
+        ));
+    }
+    document.append(
+        Path::new()
+            .set("fill", "none")
+            .set("stroke", secondary_color)
+            .set("d", polyline_data(&outline, true)),
+    );
+}
+
+/// Pegs going through the covers of two stacked racks
+fn generate_stacking_pegs(
+    document: &mut Document,
+    starting_point_x: f32,
+    starting_point_y: f32,
+    count: usize,
+    material_thickness: f32,
+    color: &str,
+) {
+    let width = STACKING_PEG_WIDTH as f32;
+    for peg in 0..count {
+        let x = starting_point_x + peg as f32 * (width + CLEARANCE_BETWEEN_PATHS as f32);
+        document.append(generate_hole_path(
+            &[
+                (x, starting_point_y),
+                (x, starting_point_y + 2.0 * material_thickness),
+                (x + width, starting_point_y + 2.0 * material_thickness),
+                (x + width, starting_point_y),
+            ],
+            color,
+        ));
+    }
 }
 
 fn generate_side_panels(
     document: &mut Document,
     starting_point_x: f32,
-    dimensions: &Dimensions,
-    rows: usize,
+    rows: &[Dimensions],
     columns: usize,
     material_thickness: f32,
+    fit_clearance: f32,
+    corner_joint: &dyn JointStyle,
+    wing_joint: &dyn JointStyle,
+    labels: &Labels,
+    options: &RackOptions,
     primary_color: &str,
     secondary_color: &str,
 ) {
+    let rack_dimensions = &rows[0];
+    let front_overhang = front_overhang(labels, material_thickness);
     for i in 0..columns + 1 {
-        let y = (i * (dimensions.depth + CLEARANCE_BETWEEN_PATHS)) as f32;
+        let y = i as f32
+            * ((rack_dimensions.depth + CLEARANCE_BETWEEN_PATHS) as f32 + front_overhang)
+            + front_overhang;
+
+        // Front edge goes from the bottom of the panel up, each row gets a slot for its
+        // retention bar at the bottom and a tab for the holder of its label at the top
+        let mut front_edge = vec![];
+        let mut row_bottom = rows_height(rows);
+        for (row, dimensions) in rows.iter().enumerate().rev() {
+            let row_top = row_bottom - dimensions.height;
+            if options.retention_bars.is_some() {
+                front_edge.extend(retention::slot(
+                    starting_point_x + material_thickness + row_bottom as f32,
+                    y,
+                    retention::bar_height(dimensions),
+                    material_thickness,
+                    fit_clearance,
+                ));
+            }
+            // Holder of a labelled slot hangs on the panel left of the slot
+            if labels.contains_key(&(row, i)) {
+                let tab = starting_point_x
+                    + material_thickness
+                    + row_top as f32
+                    + (label::LABEL_HOLDER_HEIGHT - label::LABEL_TAB_WIDTH) as f32 / 2.0;
+                let tab_end = tab + label::LABEL_TAB_WIDTH as f32;
+                front_edge.extend([
+                    (tab_end, y),
+                    (tab_end, y - material_thickness),
+                    (tab, y - material_thickness),
+                    (tab, y),
+                ]);
+            }
+            row_bottom = row_top;
+        }
 
         document.append(generate_side_panel_outline_path(
             starting_point_x,
             y,
-            dimensions,
-            rows,
+            rack_dimensions,
+            rows_height(rows),
             material_thickness,
+            corner_joint,
+            &front_edge,
+            options,
             secondary_color,
         ));
 
-        for r in 0..rows {
-            let row_x = material_thickness
-                + (dimensions.side_wing_from_box_top + r * dimensions.height) as f32;
-
-            document.append(generate_side_panel_wing_holes(
-                starting_point_x + row_x,
-                y + SIDE_WING_SLOT_FROM_FRONT as f32,
-                material_thickness,
+        if options.wall_mount == Some(WallMount::SidePanels) && (i == 0 || i == columns) {
+            // Keyhole in the middle of the depth
+            document.append(generate_hole_path(
+                &mount::keyhole(
+                    (
+                        starting_point_x
+                            + side_panel_keyhole_x(
+                                rack_dimensions,
+                                corner_joint,
+                                material_thickness,
+                                fit_clearance,
+                            ),
+                        y + rack_dimensions.depth as f32 / 2.0,
+                    ),
+                    (-1.0, 0.0),
+                ),
                 primary_color,
             ));
+        }
 
-            document.append(generate_side_panel_wing_holes(
-                starting_point_x + row_x,
-                y + (SIDE_WING_SLOT_FROM_FRONT + SIDE_WING_SLOT_WIDTH + SIDE_WING_SLOT_SPACING)
-                    as f32,
-                material_thickness,
-                primary_color,
-            ));
+        let mut row_top = 0;
+        for dimensions in rows {
+            // Slot is wider than the wing by the clearance, split evenly on both sides
+            let row_x = material_thickness + (row_top + dimensions.side_wing_from_box_top) as f32
+                - fit_clearance / 2.0;
+            row_top += dimensions.height;
 
-            document.append(generate_side_panel_wing_holes(
-                starting_point_x + row_x,
-                y + (dimensions.depth
-                    - SIDE_WING_SLOT_FROM_FRONT
-                    - (2 * SIDE_WING_SLOT_WIDTH)
-                    - SIDE_WING_SLOT_SPACING) as f32,
-                material_thickness,
-                primary_color,
-            ));
-            document.append(generate_side_panel_wing_holes(
-                starting_point_x + row_x,
-                y + (dimensions.depth - SIDE_WING_SLOT_FROM_FRONT - SIDE_WING_SLOT_WIDTH) as f32,
-                material_thickness,
-                primary_color,
-            ));
+            let (depth, slot_width) = (dimensions.depth as f32, material_thickness + fit_clearance);
+            let mut holes = wing_joint.slots(depth, slot_width);
+            holes.extend(wing_joint.female_holes(depth, slot_width));
+            for hole in &holes {
+                document.append(generate_side_panel_wing_hole(
+                    starting_point_x + row_x,
+                    y,
+                    hole,
+                    primary_color,
+                ));
+            }
         }
     }
 }
 
-fn generate_side_panel_wing_holes(x: f32, y: f32, material_thickness: f32, color: &str) -> Path {
-    let path_data = Data::new()
-        .move_to((x, y))
-        .vertical_line_to(y + SIDE_WING_SLOT_WIDTH as f32)
-        .horizontal_line_to(x + material_thickness)
-        .vertical_line_to(y)
-        .close();
+/// Distance of the round part of the keyhole from the top end of the side panel, the slot
+/// of the keyhole starts below the slot of the first wing and the joint
+fn side_panel_keyhole_x(
+    first_row: &Dimensions,
+    corner_joint: &dyn JointStyle,
+    material_thickness: f32,
+    fit_clearance: f32,
+) -> f32 {
+    let joint_depth = corner_joint
+        .male(first_row.depth as f32, material_thickness)
+        .iter()
+        .fold(material_thickness, |deepest: f32, (_, out)| {
+            deepest.max(-out)
+        });
+    let slot_end = joint_depth
+        .max(2.0 * material_thickness + fit_clearance + first_row.side_wing_from_box_top as f32)
+        + CLEARANCE_BETWEEN_PATHS as f32;
+    slot_end + mount::KEYHOLE_LENGTH
+}
 
-    Path::new()
-        .set("fill", "none")
-        .set("stroke", color)
-        .set("d", path_data)
+/// Hole of the wing joint in the side panel at `(x, y)`, slot runs along the depth and its
+/// width goes down the panel
+fn generate_side_panel_wing_hole(x: f32, y: f32, hole: &[Point], color: &str) -> Path {
+    let points = hole
+        .iter()
+        .map(|(along, out)| (x - out, y + along))
+        .collect::<Vec<Point>>();
+    generate_hole_path(&points, color)
 }
 
 fn generate_side_panel_outline_path(
     starting_point_x: f32,
     starting_point_y: f32,
     dimensions: &Dimensions,
-    rows: usize,
+    panel_inner_height: usize,
     material_thickness: f32,
+    corner_joint: &dyn JointStyle,
+    front_edge: &[Point],
+    options: &RackOptions,
     color: &str,
 ) -> Path {
-    let panel_inner_height = (dimensions.height * rows) as f32;
-    let side_panel_path_data = Data::new()
-        .move_to((starting_point_x + material_thickness, starting_point_y))
-        .vertical_line_to(starting_point_y + SIDE_TAP_FROM_FRONT as f32)
-        .horizontal_line_to(starting_point_x)
-        .vertical_line_to(starting_point_y + (SIDE_TAP_FROM_FRONT + SIDE_TAP_WIDTH) as f32)
-        .horizontal_line_to(starting_point_x + material_thickness)
-        .vertical_line_to(
-            starting_point_y + (dimensions.depth - SIDE_TAP_FROM_FRONT - SIDE_TAP_WIDTH) as f32,
-        )
-        .horizontal_line_to(starting_point_x)
-        .vertical_line_to(starting_point_y + (dimensions.depth - SIDE_TAP_FROM_FRONT) as f32)
-        .horizontal_line_to(starting_point_x + material_thickness)
-        .vertical_line_to(starting_point_y + dimensions.depth as f32)
-        .horizontal_line_to(starting_point_x + panel_inner_height + (1.0 * material_thickness))
-        .vertical_line_to(starting_point_y + (dimensions.depth - SIDE_TAP_FROM_FRONT) as f32)
-        .horizontal_line_to(starting_point_x + panel_inner_height + (2.0 * material_thickness))
-        .vertical_line_to(
-            starting_point_y + (dimensions.depth - SIDE_TAP_FROM_FRONT - SIDE_TAP_WIDTH) as f32,
-        )
-        .horizontal_line_to(starting_point_x + panel_inner_height + (1.0 * material_thickness))
-        .vertical_line_to(starting_point_y + (SIDE_TAP_FROM_FRONT + SIDE_TAP_WIDTH) as f32)
-        .horizontal_line_to(starting_point_x + panel_inner_height + (2.0 * material_thickness))
-        .vertical_line_to(starting_point_y + SIDE_TAP_FROM_FRONT as f32)
-        .horizontal_line_to(starting_point_x + panel_inner_height + (1.0 * material_thickness))
-        .vertical_line_to(starting_point_y)
-        .close();
+    let panel_inner_height = panel_inner_height as f32;
+    let depth = dimensions.depth as f32;
+    let profile = corner_joint.male(depth, material_thickness);
+    let back_x = starting_point_x + panel_inner_height + (2.0 * material_thickness);
+
+    // Taps stick out of the front end and the back end of the panel
+    let mut outline = joint::place(
+        &profile,
+        (starting_point_x, starting_point_y),
+        (starting_point_x, starting_point_y + depth),
+    );
+    outline.extend(joint::place(
+        &profile,
+        (back_x, starting_point_y + depth),
+        (back_x, starting_point_y),
+    ));
+    // Tabs and slots on the front edge
+    outline.extend(front_edge);
+    let side_panel_path_data = polyline_data(&outline, true);
 
     Path::new()
         .set("fill", "none")
         .set("stroke", color)
-        .set("d", side_panel_path_data)
+        .set("d", relieve_outline(side_panel_path_data, options))
 }
 
 fn generate_top_and_bottom_pieces(
     document: &mut Document,
     dimensions: &Dimensions,
     starting_point_x: f32,
-    columns: usize,
-    column_width: f32,
+    column_pitches: &[f32],
     material_thickness: f32,
+    fit_clearance: f32,
+    corner_joint: &dyn JointStyle,
+    options: &RackOptions,
     primary_color: &str,
     secondary_color: &str,
 ) {
@@ -249,9 +1246,12 @@ fn generate_top_and_bottom_pieces(
         dimensions,
         starting_point_x,
         0.0,
-        columns,
-        column_width,
+        true,
+        column_pitches,
         material_thickness,
+        fit_clearance,
+        corner_joint,
+        options,
         primary_color,
         secondary_color,
     );
@@ -261,9 +1261,12 @@ fn generate_top_and_bottom_pieces(
         dimensions,
         starting_point_x,
         (dimensions.depth + CLEARANCE_BETWEEN_PATHS) as f32,
-        columns,
-        column_width,
+        false,
+        column_pitches,
         material_thickness,
+        fit_clearance,
+        corner_joint,
+        options,
         primary_color,
         secondary_color,
     );
@@ -274,99 +1277,137 @@ fn generate_cover_path(
     dimensions: &Dimensions,
     starting_point_x: f32,
     starting_point_y: f32,
-    columns: usize,
-    column_width: f32,
+    top: bool,
+    column_pitches: &[f32],
     material_thickness: f32,
+    fit_clearance: f32,
+    corner_joint: &dyn JointStyle,
+    options: &RackOptions,
     primary_color: &str,
     secondary_color: &str,
 ) {
+    let slot_width = material_thickness + fit_clearance;
     // Generate cover
     let top_path_data = generate_top_path(
         dimensions,
         starting_point_x,
         starting_point_y,
-        columns,
-        column_width,
+        column_pitches,
         material_thickness,
+        corner_joint,
     );
     let path = Path::new()
         .set("fill", "none")
         .set("stroke", secondary_color)
-        .set("d", top_path_data);
+        .set("d", relieve_outline(top_path_data, options));
     document.append(path);
 
-    for i in 0..columns - 1 {
-        let x = starting_point_x + column_width + (i as f32 * column_width);
-        let y = starting_point_y + SIDE_TAP_FROM_FRONT as f32;
-        let side_tap_hole_path = generate_side_tap_path(x, y, material_thickness, primary_color);
-        document.append(side_tap_hole_path);
+    let depth = dimensions.depth as f32;
+    let top_width = top_width(column_pitches, material_thickness);
+    let front = starting_point_y;
+    let back = starting_point_y + depth;
 
-        let side_tap_hole_path = generate_side_tap_path(
-            x,
-            y + (dimensions.depth - SIDE_TAP_FROM_FRONT - (SIDE_TAP_WIDTH * 2)) as f32,
-            material_thickness,
-            primary_color,
-        );
-        document.append(side_tap_hole_path);
+    // Holes next to the edges for the outer side panels, e.g. for bolts
+    let holes = corner_joint.female_holes(depth, material_thickness);
+    for (start, end) in [
+        ((starting_point_x, front), (starting_point_x, back)),
+        (
+            (starting_point_x + top_width, back),
+            (starting_point_x + top_width, front),
+        ),
+    ] {
+        for hole in &holes {
+            document.append(generate_hole_path(
+                &joint::place(hole, start, end),
+                primary_color,
+            ));
+        }
     }
 
-    //Generate side panel taps to middle of cover
-}
+    // Inner side panels go through the cover
+    let mut holes = corner_joint.slots(depth, slot_width);
+    holes.extend(corner_joint.female_holes(depth, slot_width));
+    let mut x = starting_point_x;
+    for column_pitch in &column_pitches[..column_pitches.len() - 1] {
+        x += column_pitch;
+        let slot_x = x - fit_clearance / 2.0;
+        for hole in &holes {
+            let side_tap_hole_path = generate_hole_path(
+                &joint::place(hole, (slot_x, front), (slot_x, back)),
+                primary_color,
+            );
+            document.append(side_tap_hole_path);
+        }
+    }
 
-fn generate_side_tap_path(x: f32, y: f32, material_thickness: f32, color: &str) -> Path {
-    let data = Data::new()
-        .move_to((x, y))
-        .vertical_line_to(y + SIDE_TAP_WIDTH as f32)
-        .horizontal_line_to(x + material_thickness as f32)
-        .vertical_line_to(y)
-        .close();
+    // Tabs of the back stretcher stand in the top cover, in front of its back edge
+    if top && options.wall_mount == Some(WallMount::BackStretcher) {
+        let half_tab = BACK_STRETCHER_TAB_WIDTH as f32 / 2.0;
+        let slot_y = back - 2.0 * material_thickness - fit_clearance / 2.0;
+        for center in column_centers(column_pitches, material_thickness) {
+            let x = starting_point_x + center;
+            document.append(generate_hole_path(
+                &[
+                    (x - half_tab, slot_y),
+                    (x - half_tab, slot_y + slot_width),
+                    (x + half_tab, slot_y + slot_width),
+                    (x + half_tab, slot_y),
+                ],
+                primary_color,
+            ));
+        }
+    }
 
-    Path::new()
-        .set("fill", "none")
-        .set("stroke", color)
-        .set("d", data)
+    if options.stacking {
+        let half_peg = STACKING_PEG_WIDTH as f32 / 2.0;
+        for (x, y) in stacking_peg_positions(dimensions.depth, column_pitches, material_thickness) {
+            let (x, y) = (
+                starting_point_x + x - slot_width / 2.0,
+                front + y - half_peg,
+            );
+            document.append(generate_hole_path(
+                &[
+                    (x, y),
+                    (x, y + 2.0 * half_peg),
+                    (x + slot_width, y + 2.0 * half_peg),
+                    (x + slot_width, y),
+                ],
+                primary_color,
+            ));
+        }
+    }
+
+    //Generate side panel taps to middle of cover
 }
 
 fn generate_top_path(
     dimensions: &Dimensions,
     starting_point_x: f32,
     starting_point_y: f32,
-    columns: usize,
-    column_width: f32,
+    column_pitches: &[f32],
     material_thickness: f32,
+    corner_joint: &dyn JointStyle,
 ) -> Data {
-    let top_width = top_width(column_width, columns, material_thickness);
+    let top_width = top_width(column_pitches, material_thickness);
+    let depth = dimensions.depth as f32;
+    let profile = corner_joint.female(depth, material_thickness);
 
-    Data::new()
-        .move_to((starting_point_x, starting_point_y))
-        .vertical_line_to(starting_point_y + SIDE_TAP_FROM_FRONT as f32)
-        .horizontal_line_to(starting_point_x + material_thickness)
-        .vertical_line_to(starting_point_y + (SIDE_TAP_FROM_FRONT + SIDE_TAP_WIDTH) as f32)
-        .horizontal_line_to(starting_point_x)
-        .vertical_line_to(
-            starting_point_y + (dimensions.depth - (SIDE_TAP_FROM_FRONT + SIDE_TAP_WIDTH)) as f32,
-        )
-        .horizontal_line_to(starting_point_x + material_thickness)
-        .vertical_line_to(starting_point_y + (dimensions.depth - SIDE_TAP_FROM_FRONT) as f32)
-        .horizontal_line_to(starting_point_x)
-        .vertical_line_to(starting_point_y + dimensions.depth as f32)
-        .horizontal_line_to(starting_point_x + top_width)
-        .vertical_line_to(starting_point_y + (dimensions.depth - SIDE_TAP_FROM_FRONT) as f32)
-        .horizontal_line_to(starting_point_x - material_thickness + top_width)
-        .vertical_line_to(
-            starting_point_y + (dimensions.depth - (SIDE_TAP_FROM_FRONT + SIDE_TAP_WIDTH)) as f32,
-        )
-        .horizontal_line_to(starting_point_x + top_width)
-        .vertical_line_to(starting_point_y + (SIDE_TAP_FROM_FRONT + SIDE_TAP_WIDTH) as f32)
-        .horizontal_line_to(starting_point_x - material_thickness + top_width)
-        .vertical_line_to(starting_point_y + SIDE_TAP_FROM_FRONT as f32)
-        .horizontal_line_to(starting_point_x + top_width)
-        .vertical_line_to(starting_point_y)
-        .close()
+    // Taps of the outer side panels go into the notches on the left and right edge
+    let mut outline = joint::place(
+        &profile,
+        (starting_point_x, starting_point_y),
+        (starting_point_x, starting_point_y + depth),
+    );
+    outline.extend(joint::place(
+        &profile,
+        (starting_point_x + top_width, starting_point_y + depth),
+        (starting_point_x + top_width, starting_point_y),
+    ));
+    polyline_data(&outline, true)
 }
 
-fn top_width(column_width: f32, columns: usize, material_thickness: f32) -> f32 {
-    (material_thickness + column_width * columns as f32) + material_thickness
+fn top_width(column_pitches: &[f32], material_thickness: f32) -> f32 {
+    (material_thickness + column_pitches.iter().sum::<f32>()) + material_thickness
 }
 fn generate_side_wing_pair(
     document: &mut Document,
@@ -374,6 +1415,9 @@ fn generate_side_wing_pair(
     starting_point_x: f32,
     starting_point_y: f32,
     material_thickness: f32,
+    fit_clearance: f32,
+    wing_joint: &dyn JointStyle,
+    stop: Option<(DrawerStop, f32)>,
     color: &str,
 ) {
     let path = generate_side_wing(
@@ -382,20 +1426,50 @@ fn generate_side_wing_pair(
         material_thickness,
         dimensions.depth,
         dimensions.side_wing_width,
+        wing_joint,
         false,
-        &color,
+        stop,
+        color,
     );
     document.append(path);
+    let inverted_y =
+        starting_point_y + (dimensions.side_wing_width + CLEARANCE_BETWEEN_PATHS) as f32;
     let path = generate_side_wing(
         starting_point_x,
-        starting_point_y + (dimensions.side_wing_width + CLEARANCE_BETWEEN_PATHS) as f32,
+        inverted_y,
         material_thickness,
         dimensions.depth,
         dimensions.side_wing_width,
+        wing_joint,
         true,
-        &color,
+        stop,
+        color,
     );
     document.append(path);
+
+    if let Some((DrawerStop::Tab, _)) = stop {
+        // Free edge and the base of the taps of both wings
+        let width = dimensions.side_wing_width as f32;
+        let edges = [
+            (starting_point_y, starting_point_y + width),
+            (
+                inverted_y + material_thickness + width,
+                inverted_y + material_thickness,
+            ),
+        ];
+        for (edge_y, base_y) in edges {
+            let slot: Vec<Point> = stop::tab_slot(
+                dimensions.depth,
+                edge_y,
+                base_y,
+                material_thickness + fit_clearance,
+            )
+            .into_iter()
+            .map(|(x, y)| (starting_point_x + x, y))
+            .collect();
+            document.append(generate_hole_path(&slot, color));
+        }
+    }
 }
 
 fn height_of_two_side_wings(side_wing_width: usize, material_thickness: f32) -> f32 {
@@ -408,7 +1482,9 @@ fn generate_side_wing(
     material_thickness: f32,
     box_depth: usize,
     box_side_wing_width: usize,
+    wing_joint: &dyn JointStyle,
     inverted: bool,
+    stop: Option<(DrawerStop, f32)>,
     color: &str,
 ) -> Path {
     let wing_data = if inverted {
@@ -418,6 +1494,8 @@ fn generate_side_wing(
             material_thickness,
             box_depth,
             box_side_wing_width,
+            wing_joint,
+            stop,
         )
     } else {
         generate_side_wing_path(
@@ -426,6 +1504,8 @@ fn generate_side_wing(
             material_thickness,
             box_depth,
             box_side_wing_width,
+            wing_joint,
+            stop,
         )
     };
 
@@ -435,29 +1515,50 @@ fn generate_side_wing(
         .set("d", wing_data)
 }
 
+/// Tabs of one wing of the pair, the first wing takes the first tab and every other after it
+fn wing_tabs(
+    wing_joint: &dyn JointStyle,
+    box_depth: usize,
+    material_thickness: f32,
+    second: bool,
+) -> Vec<(f32, f32)> {
+    wing_joint
+        .tabs(box_depth as f32, material_thickness)
+        .into_iter()
+        .skip(usize::from(second))
+        .step_by(2)
+        .collect()
+}
+
 fn generate_side_wing_path(
     starting_point_x: f32,
     starting_point_y: f32,
     material_thickness: f32,
     box_depth: usize,
     box_side_wing_width: usize,
+    wing_joint: &dyn JointStyle,
+    stop: Option<(DrawerStop, f32)>,
 ) -> Data {
-    Data::new()
+    let base_y = starting_point_y + box_side_wing_width as f32;
+    let mut data = Data::new()
         .move_to((starting_point_x, starting_point_y))
-        .vertical_line_to(starting_point_y + box_side_wing_width as f32)
-        .horizontal_line_to(SIDE_WING_SLOT_FROM_FRONT)
-        .vertical_line_to(starting_point_y + material_thickness + box_side_wing_width as f32)
-        .horizontal_line_to(SIDE_WING_SLOT_FROM_FRONT + SIDE_WING_SLOT_WIDTH)
-        .vertical_line_to(starting_point_y + box_side_wing_width as f32)
-        .horizontal_line_to(third_side_wing_tap_position_from_front(box_depth))
-        .vertical_line_to(starting_point_y + box_side_wing_width as f32 + material_thickness)
-        .horizontal_line_to(
-            third_side_wing_tap_position_from_front(box_depth) + SIDE_WING_SLOT_WIDTH,
-        )
-        .vertical_line_to(starting_point_y + box_side_wing_width as f32)
+        .vertical_line_to(base_y);
+    for (from, to) in wing_tabs(wing_joint, box_depth, material_thickness, false) {
+        data = data
+            .horizontal_line_to(from)
+            .vertical_line_to(base_y + material_thickness)
+            .horizontal_line_to(to)
+            .vertical_line_to(base_y);
+    }
+    let data = data
         .horizontal_line_to(box_depth)
-        .vertical_line_to(starting_point_y)
-        .close()
+        .vertical_line_to(starting_point_y);
+    match stop {
+        Some((DrawerStop::Notch, depth)) => {
+            stop::notch_edge(data, depth, box_depth, starting_point_y, 1.0).close()
+        }
+        _ => data.close(),
+    }
 }
 
 fn generate_side_wing_inverted_path(
@@ -466,34 +1567,430 @@ fn generate_side_wing_inverted_path(
     material_thickness: f32,
     box_depth: usize,
     box_side_wing_width: usize,
+    wing_joint: &dyn JointStyle,
+    stop: Option<(DrawerStop, f32)>,
 ) -> Data {
-    Data::new()
-        .move_to((starting_point_x, starting_point_y + material_thickness))
-        .horizontal_line_to(second_side_wing_tap_position_from_front())
-        .vertical_line_to(starting_point_y)
-        .horizontal_line_to(second_side_wing_tap_position_from_front() + SIDE_WING_SLOT_WIDTH)
-        .vertical_line_to(starting_point_y + material_thickness)
-        .horizontal_line_to(fourth_side_wing_tap_position_from_front(box_depth))
-        .vertical_line_to(starting_point_y)
-        .horizontal_line_to(box_depth - SIDE_WING_SLOT_FROM_FRONT)
-        .vertical_line_to(starting_point_y + material_thickness)
-        .horizontal_line_to(box_depth)
-        .vertical_line_to(starting_point_y + material_thickness + box_side_wing_width as f32)
-        .horizontal_line_to(starting_point_x)
-        .close()
+    let base_y = starting_point_y + material_thickness;
+    let edge_y = base_y + box_side_wing_width as f32;
+    let mut data = Data::new().move_to((starting_point_x, base_y));
+    for (from, to) in wing_tabs(wing_joint, box_depth, material_thickness, true) {
+        data = data
+            .horizontal_line_to(from)
+            .vertical_line_to(starting_point_y)
+            .horizontal_line_to(to)
+            .vertical_line_to(base_y);
+    }
+    let data = data.horizontal_line_to(box_depth).vertical_line_to(edge_y);
+    let data = match stop {
+        Some((DrawerStop::Notch, depth)) => stop::notch_edge(data, depth, box_depth, edge_y, -1.0),
+        _ => data,
+    };
+    data.horizontal_line_to(starting_point_x).close()
 }
 
-fn third_side_wing_tap_position_from_front(box_depth: usize) -> usize {
-    box_depth
-        - (SIDE_WING_SLOT_FROM_FRONT
-            + SIDE_WING_SLOT_WIDTH
-            + SIDE_WING_SLOT_SPACING
-            + SIDE_WING_SLOT_WIDTH)
-}
-fn second_side_wing_tap_position_from_front() -> usize {
-    SIDE_WING_SLOT_FROM_FRONT + SIDE_WING_SLOT_WIDTH + SIDE_WING_SLOT_SPACING
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geometry::{document_parts, Part};
+    use joint::StraightTabs;
+
+    fn classic_1() -> Dimensions {
+        Dimensions {
+            width: 170,
+            depth: 210,
+            height: 56,
+            side_wing_from_box_top: 15,
+            side_wing_width: 8,
+        }
+    }
+
+    #[test]
+    fn validate_accepts_rows_of_same_footprint() {
+        let tall = Dimensions {
+            height: 112,
+            ..classic_1()
+        };
+        let layout = Layout::with_rows(vec![classic_1(), tall], 2);
+        assert_eq!(layout.validate(), Ok(()));
+    }
+
+    #[test]
+    fn validate_rejects_empty_layout() {
+        assert_eq!(
+            Layout::with_rows(vec![], 2).validate(),
+            Err(LayoutError::NoRows)
+        );
+        assert_eq!(
+            Layout::uniform(&classic_1(), 2, 0).validate(),
+            Err(LayoutError::NoColumns)
+        );
+    }
+
+    #[test]
+    fn validate_rejects_zero_width_column() {
+        let mut layout = Layout::uniform(&classic_1(), 1, 3);
+        layout.column_widths[1] = 0;
+        assert_eq!(
+            layout.validate(),
+            Err(LayoutError::ZeroWidthColumn { column: 2 })
+        );
+    }
+
+    #[test]
+    fn validate_rejects_row_with_other_footprint() {
+        let deeper = Dimensions {
+            depth: 300,
+            ..classic_1()
+        };
+        let layout = Layout::with_rows(vec![classic_1(), deeper], 1);
+        assert!(matches!(
+            layout.validate(),
+            Err(LayoutError::IncompatibleRow { row: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn empty_layout_is_an_error_not_a_panic() {
+        let layout = Layout::with_rows(vec![], 0);
+        assert_eq!(layout.depth(), 0);
+        assert!(generate_layout_svg(&layout, 4.0, "black", "blue").is_err());
+        assert!(generate_svg_with_rows(&[classic_1()], 0, 4.0, "black", "blue").is_err());
+
+        let container = &crate::supported_containers()[0];
+        assert_eq!(
+            generate_svg(0, 2, 4.0, container, "black", "blue").err(),
+            Some(LayoutError::NoRows)
+        );
+        assert!(generate_svg(1, 2, 4.0, container, "black", "blue").is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_layout_over_limits() {
+        let wide = Layout::with_rows(vec![classic_1()], 1);
+        let wide = Layout {
+            column_widths: vec![MAX_SIZE + 1],
+            ..wide
+        };
+        assert_eq!(
+            wide.validate(),
+            Err(LayoutError::ColumnTooWide {
+                column: 1,
+                width: MAX_SIZE + 1
+            })
+        );
+        assert_eq!(
+            Layout::uniform(&classic_1(), MAX_ROWS + 1, 1).validate(),
+            Err(LayoutError::TooManyRows(MAX_ROWS + 1))
+        );
+    }
+
+    #[test]
+    fn relief_that_does_not_fit_in_slots_is_an_error() {
+        let mut layout = Layout::uniform(&classic_1(), 2, 2);
+        layout.options.relief = Some(ReliefStyle::Dogbone);
+        assert!(generate_layout_svg(&layout, 4.0, "black", "blue").is_ok());
+
+        layout.options.tool_diameter = 12.0;
+        assert!(matches!(
+            generate_layout_svg(&layout, 4.0, "black", "blue"),
+            Err(LayoutError::ReliefDoesNotFit { .. })
+        ));
+    }
+
+    #[test]
+    fn wing_joint_style_sets_wing_tabs_and_panel_slots() {
+        let mut layout = Layout::uniform(&classic_1(), 1, 1);
+        let default = document_parts(&generate_layout_svg(&layout, 4.0, "black", "blue").unwrap());
+        layout.wing_joint_style = Some(Arc::new(StraightTabs {
+            from_end: 20.0,
+            width: 20.0,
+        }));
+        let own = document_parts(&generate_layout_svg(&layout, 4.0, "black", "blue").unwrap());
+
+        // Two side panels lose two of their four slots, each wing keeps one tab
+        let holes = |parts: &[Part]| parts.iter().map(|part| part.holes.len()).sum::<usize>();
+        assert_eq!(holes(&default) - holes(&own), 4);
+        assert_eq!(own.len(), default.len());
+    }
+
+    #[test]
+    fn validate_material_rejects_bolt_hole_wider_than_material() {
+        let mut layout = Layout::uniform(&classic_1(), 1, 1);
+        layout.options.corner_joint = CornerJoint::TSlot;
+        assert_eq!(layout.validate_material(4.0), Ok(()));
+
+        layout.options.screw = MetricScrew::M4;
+        assert_eq!(
+            layout.validate_material(4.0),
+            Err(LayoutError::BoltHoleTooWide(MetricScrew::M4))
+        );
+        assert!(generate_layout_svg(&layout, 4.0, "black", "blue").is_err());
+        assert_eq!(layout.validate_material(5.0), Ok(()));
+    }
+
+    #[test]
+    fn validate_bounds_drawer_stop_depth_by_the_wing() {
+        let mut layout = Layout::uniform(&classic_1(), 2, 1);
+        layout.options.drawer_stop = Some(DrawerStop::Notch);
+        layout.options.drawer_stop_depth = 4.0;
+        assert_eq!(layout.validate(), Ok(()));
+        layout.options.drawer_stop_depth = 4.5;
+        assert_eq!(
+            layout.validate(),
+            Err(LayoutError::DrawerStopTooDeep {
+                row: 1,
+                side_wing_width: 8
+            })
+        );
+
+        layout.options.drawer_stop = Some(DrawerStop::Tab);
+        layout.options.drawer_stop_depth = 15.0;
+        assert_eq!(layout.validate(), Ok(()));
+        layout.options.drawer_stop_depth = 15.5;
+        assert_eq!(
+            layout.validate(),
+            Err(LayoutError::DrawerStopTooTall {
+                row: 1,
+                side_wing_from_box_top: 15
+            })
+        );
+
+        layout.options.drawer_stop_depth = 0.0;
+        assert_eq!(layout.validate(), Err(LayoutError::InvalidDrawerStopDepth));
+    }
+
+    #[test]
+    fn validate_material_rejects_row_lower_than_its_wing() {
+        let low = Dimensions {
+            height: 18,
+            ..classic_1()
+        };
+        let layout = Layout::with_rows(vec![classic_1(), low], 2);
+        assert_eq!(layout.validate_material(3.0), Ok(()));
+        assert_eq!(
+            layout.validate_material(3.5),
+            Err(LayoutError::RowTooShort {
+                row: 2,
+                height: 18,
+                min_height: 19
+            })
+        );
+    }
+
+    #[test]
+    fn validate_material_rejects_tab_stop_slot_wider_than_half_the_wing() {
+        let mut layout = Layout::uniform(&classic_1(), 1, 1);
+        layout.options.drawer_stop = Some(DrawerStop::Tab);
+        assert_eq!(layout.validate_material(4.0), Ok(()));
+        assert_eq!(
+            layout.validate_material(4.5),
+            Err(LayoutError::DrawerStopSlotTooWide {
+                row: 1,
+                side_wing_width: 8
+            })
+        );
+    }
+
+    #[test]
+    fn tab_stop_is_a_piece_in_a_slot_of_every_wing() {
+        let mut layout = Layout::uniform(&classic_1(), 2, 2);
+        let plain = document_parts(&generate_layout_svg(&layout, 4.0, "black", "blue").unwrap());
+        layout.options.drawer_stop = Some(DrawerStop::Tab);
+        let parts = document_parts(&generate_layout_svg(&layout, 4.0, "black", "blue").unwrap());
+
+        // Wings keep their outline and get a slot, one stop piece for every wing
+        let wings = 2 * 2 * 2;
+        assert_eq!(parts.len(), plain.len() + wings);
+        let slotted = parts
+            .iter()
+            .zip(&plain)
+            .filter(|(part, plain)| {
+                part.outline == plain.outline && part.holes.len() == plain.holes.len() + 1
+            })
+            .count();
+        assert_eq!(slotted, wings);
+    }
+
+    fn size(part: &Part) -> (f32, f32) {
+        let (min_x, min_y, max_x, max_y) = part.bounding_box();
+        (max_x - min_x, max_y - min_y)
+    }
+
+    fn hole_ranges(part: &Part, size: (f32, f32)) -> Vec<(f32, f32)> {
+        let (min_x, _, _, _) = part.bounding_box();
+        let mut ranges = part
+            .holes
+            .iter()
+            .map(|hole| geometry::bounding_box(&hole.points))
+            .filter(|(x0, y0, x1, y1)| {
+                (x1 - x0 - size.0).abs() < 0.01 && (y1 - y0 - size.1).abs() < 0.01
+            })
+            .map(|(x0, _, x1, _)| (x0 - min_x, x1 - min_x))
+            .collect::<Vec<_>>();
+        ranges.sort_by(|a, b| a.0.total_cmp(&b.0));
+        ranges
+    }
+
+    #[test]
+    fn keyhole_must_fit_in_the_first_row() {
+        let short = Dimensions {
+            height: 30,
+            ..classic_1()
+        };
+        let mut layout = Layout::with_rows(vec![short, classic_1()], 2);
+        layout.options.wall_mount = Some(WallMount::SidePanels);
+        assert_eq!(
+            generate_layout_svg(&layout, 4.0, "black", "blue").err(),
+            Some(LayoutError::KeyholeDoesNotFit { row_height: 30 })
+        );
+
+        layout.rows.reverse();
+        let parts = document_parts(&generate_layout_svg(&layout, 4.0, "black", "blue").unwrap());
+        // Outer side panels get a keyhole each, inner one only its wing slots
+        let panels = parts
+            .iter()
+            .filter(|part| size(part).0 == (56 + 30) as f32 + 8.0)
+            .map(|part| part.holes.len())
+            .collect::<Vec<usize>>();
+        assert_eq!(panels, vec![9, 8, 9]);
+    }
+
+    #[test]
+    fn stacking_pegs_fit_the_holes_in_both_covers() {
+        let layout = Layout {
+            options: RackOptions {
+                stacking: true,
+                ..RackOptions::default()
+            },
+            ..Layout::uniform(&classic_1(), 1, 3)
+        };
+        let pitches = layout.column_pitches(4.0);
+        let positions = stacking_peg_positions(210, &pitches, 4.0);
+        let centers = column_centers(&pitches, 4.0);
+        assert_eq!(
+            positions,
+            vec![
+                (centers[0], 45.0),
+                (centers[0], 165.0),
+                (centers[2], 45.0),
+                (centers[2], 165.0)
+            ]
+        );
+        assert_eq!(stacking_peg_positions(210, &pitches[..1], 4.0).len(), 2);
 
-fn fourth_side_wing_tap_position_from_front(box_depth: usize) -> usize {
-    box_depth - (SIDE_WING_SLOT_FROM_FRONT + SIDE_WING_SLOT_WIDTH)
+        let parts = document_parts(&generate_layout_svg(&layout, 4.0, "black", "blue").unwrap());
+        let (top_width, _, _) = layout.outer_size(4.0);
+        let covers = parts
+            .iter()
+            .filter(|part| size(part).0 == top_width)
+            .collect::<Vec<&Part>>();
+        assert_eq!(covers.len(), 2);
+        for cover in covers {
+            // Holes as long as the peg and as wide as the material, clear of the slots
+            let holes = hole_ranges(cover, (4.0, STACKING_PEG_WIDTH as f32));
+            assert_eq!(holes.len(), 4);
+            assert!(cover.holes.iter().enumerate().all(|(i, a)| {
+                cover.holes[i + 1..].iter().all(|b| {
+                    let (a, b) = (
+                        geometry::bounding_box(&a.points),
+                        geometry::bounding_box(&b.points),
+                    );
+                    a.2 <= b.0 || b.2 <= a.0 || a.3 <= b.1 || b.3 <= a.1
+                })
+            }));
+        }
+        let pegs = parts
+            .iter()
+            .filter(|part| size(part) == (STACKING_PEG_WIDTH as f32, 8.0))
+            .count();
+        assert_eq!(pegs, 4);
+    }
+
+    #[test]
+    fn back_stretcher_tabs_stand_in_the_top_cover_slots() {
+        let layout = Layout {
+            options: RackOptions {
+                wall_mount: Some(WallMount::BackStretcher),
+                ..RackOptions::default()
+            },
+            ..Layout::uniform(&classic_1(), 1, 3)
+        };
+        let parts = document_parts(&generate_layout_svg(&layout, 4.0, "black", "blue").unwrap());
+        let (top_width, _, _) = layout.outer_size(4.0);
+        let stretcher = parts
+            .iter()
+            .find(|part| size(part) == (top_width, BACK_STRETCHER_HEIGHT as f32 + 4.0))
+            .unwrap();
+        assert_eq!(stretcher.holes.len(), 3);
+
+        let (min_x, _, _, max_y) = stretcher.bounding_box();
+        let mut tab_ends = stretcher
+            .outline
+            .points
+            .iter()
+            .filter(|(_, y)| *y == max_y)
+            .map(|(x, _)| x - min_x)
+            .collect::<Vec<f32>>();
+        tab_ends.sort_by(f32::total_cmp);
+        let tabs = tab_ends
+            .chunks(2)
+            .map(|tab| (tab[0], tab[1]))
+            .collect::<Vec<_>>();
+
+        let slots = parts
+            .iter()
+            .filter(|part| size(part).0 == top_width)
+            .map(|cover| hole_ranges(cover, (BACK_STRETCHER_TAB_WIDTH as f32, 4.0)))
+            .find(|slots| !slots.is_empty())
+            .unwrap();
+        assert_eq!(tabs.len(), 3);
+        assert_eq!(slots, tabs);
+    }
+
+    #[test]
+    fn label_tab_is_on_the_panel_left_of_the_slot() {
+        let mut layout = Layout::uniform(&classic_1(), 2, 3);
+        layout.labels.insert((1, 2), "Screws".to_string());
+        let material = Material {
+            fit_clearance: 0.3,
+            ..Material::from(4.0)
+        };
+        let parts =
+            document_parts(&generate_layout_svg(&layout, &material, "black", "blue").unwrap());
+
+        // Panels are laid out from the left one, only the third one has a tab in front
+        let mut panels = parts
+            .iter()
+            .filter(|part| size(part).0 == 2.0 * 56.0 + 8.0)
+            .collect::<Vec<&Part>>();
+        panels.sort_by(|a, b| a.bounding_box().1.total_cmp(&b.bounding_box().1));
+        let depths = panels
+            .iter()
+            .map(|panel| size(panel).1)
+            .collect::<Vec<f32>>();
+        assert_eq!(depths.len(), 4);
+        assert_eq!(depths[2], depths[0] + 4.0);
+        assert!([0, 1, 3].iter().all(|index| depths[*index] == depths[0]));
+
+        // Tab is in the middle of the holder hanging from the top of the second row
+        let (min_x, min_y, _, _) = panels[2].bounding_box();
+        let mut tab = panels[2]
+            .outline
+            .points
+            .iter()
+            .filter(|(_, y)| *y == min_y)
+            .map(|(x, _)| x - min_x)
+            .collect::<Vec<f32>>();
+        tab.sort_by(f32::total_cmp);
+        assert_eq!(tab, vec![4.0 + 56.0 + 3.5, 4.0 + 56.0 + 3.5 + 8.0]);
+
+        // Holder slot takes the tab with the fit clearance
+        let holder = parts
+            .iter()
+            .find(|part| size(part) == (40.0, 15.0))
+            .unwrap();
+        let (x0, _, x1, _) = geometry::bounding_box(&holder.holes[0].points);
+        assert!((x1 - x0 - 4.3).abs() < 0.001);
+        assert_eq!(holder.engravings.len(), 1);
+    }
 }
@@ -0,0 +1,98 @@
+//! Mounting the rack on a wall and stacking racks on each other.
+//!
+//! Keyhole slots take a screw head through the round part, the rack then drops down so the
+//! screw sits in the narrow slot. Stacked racks register with short pegs going through the
+//! bottom cover of the upper rack and the top cover of the lower one.
+
+use std::f32::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+
+use super::geometry::Point;
+
+pub const KEYHOLE_HEAD_DIAMETER: f32 = 10.0;
+const KEYHOLE_SLOT_WIDTH: f32 = 5.0;
+/// Distance between the center of the round part and the end of the slot
+pub const KEYHOLE_LENGTH: f32 = 15.0;
+
+/// Part of the rack with the keyhole slots
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub enum WallMount {
+    /// Board standing on the back edge of the top cover, one keyhole for every column
+    BackStretcher,
+    /// Outer side panels, e.g. for fixing the rack to the side of a cabinet
+    SidePanels,
+}
+
+/// Keyhole slot with the round part at `center` and the slot going towards `up`, which is
+/// a unit vector.
+pub fn keyhole(center: Point, up: Point) -> Vec<Point> {
+    let (head, slot) = (KEYHOLE_HEAD_DIAMETER / 2.0, KEYHOLE_SLOT_WIDTH / 2.0);
+    let slot_length = KEYHOLE_LENGTH - slot;
+    let across = (-up.1, up.0);
+    let at = |along: f32, side: f32| {
+        (
+            center.0 + along * up.0 + side * across.0,
+            center.1 + along * up.1 + side * across.1,
+        )
+    };
+
+    // Round part from one side of the slot around to the other, about 15 degrees apart
+    let junction = slot.atan2((head * head - slot * slot).sqrt());
+    let steps = 24;
+    let mut points = (0..=steps)
+        .map(|step| {
+            let angle = junction + (2.0 * PI - 2.0 * junction) * step as f32 / steps as f32;
+            at(head * angle.cos(), head * angle.sin())
+        })
+        .collect::<Vec<Point>>();
+    // Rounded end of the slot
+    points.extend((0..=12).map(|step| {
+        let angle = -PI / 2.0 + PI * step as f32 / 12.0;
+        at(slot_length + slot * angle.cos(), slot * angle.sin())
+    }));
+    points
+}
+
+impl FromStr for WallMount {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "back-stretcher" | "stretcher" => Ok(WallMount::BackStretcher),
+            "side-panels" | "sides" => Ok(WallMount::SidePanels),
+            _ => Err(format!(
+                "Unknown wall mount '{}', use back-stretcher or side-panels",
+                value
+            )),
+        }
+    }
+}
+
+impl fmt::Display for WallMount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WallMount::BackStretcher => write!(f, "back-stretcher"),
+            WallMount::SidePanels => write!(f, "side-panels"),
+        }
+    }
+}
+
+impl TryFrom<String> for WallMount {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<WallMount> for String {
+    fn from(mount: WallMount) -> Self {
+        mount.to_string()
+    }
+}
@@ -0,0 +1,305 @@
+//! Placing parts on sheets of material.
+//!
+//! Parts are packed as rectangles on shelves, tallest first. It's not optimal, but predictable
+//! and good enough for estimating how much material is needed.
+
+use std::fmt;
+use std::str::FromStr;
+
+use svg::{Document, Node};
+
+use super::bom::BomItem;
+use super::geometry::{contour_path, Part};
+use super::CLEARANCE_BETWEEN_PATHS;
+
+/// Size of a sheet of material in mm
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub struct SheetSize {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for SheetSize {
+    fn default() -> Self {
+        SheetSize {
+            width: 600.0,
+            height: 400.0,
+        }
+    }
+}
+
+impl FromStr for SheetSize {
+    type Err = String;
+
+    /// Parse size like `600x400`
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let error = || format!("Invalid sheet size '{}', expected e.g. `600x400`", value);
+        let (width, height) = value.trim().split_once(['x', 'X']).ok_or_else(error)?;
+        let width = width.trim().parse::<f32>().map_err(|_| error())?;
+        let height = height.trim().parse::<f32>().map_err(|_| error())?;
+        if width <= 0.0 || height <= 0.0 {
+            return Err(error());
+        }
+
+        Ok(SheetSize { width, height })
+    }
+}
+
+impl TryFrom<String> for SheetSize {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<SheetSize> for String {
+    fn from(size: SheetSize) -> Self {
+        size.to_string()
+    }
+}
+
+impl fmt::Display for SheetSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+/// Where a part ends up, position is the top left corner of the part after rotation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placement {
+    pub sheet: usize,
+    pub x: f32,
+    pub y: f32,
+    /// Part is turned 90 degrees
+    pub rotated: bool,
+}
+
+/// Part doesn't fit on the sheet even when rotated
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartTooLarge {
+    pub width: f32,
+    pub height: f32,
+    pub sheet: SheetSize,
+}
+
+impl fmt::Display for PartTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Part of {}x{}mm doesn't fit on {}mm sheet",
+            self.width, self.height, self.sheet
+        )
+    }
+}
+
+impl std::error::Error for PartTooLarge {}
+
+/// Pack parts given as `(width, height)` on sheets, placements are in the same order as parts
+pub fn pack(parts: &[(f32, f32)], sheet: &SheetSize) -> Result<Vec<Placement>, PartTooLarge> {
+    let clearance = CLEARANCE_BETWEEN_PATHS as f32;
+    let fits = |width: f32, height: f32| width <= sheet.width && height <= sheet.height;
+
+    // Lay every part flat, longer side along the sheet width when possible
+    let mut oriented = parts
+        .iter()
+        .enumerate()
+        .map(|(index, (width, height))| {
+            let rotated = if fits(*width, *height) && fits(*height, *width) {
+                (height > width) == (sheet.width >= sheet.height)
+            } else if fits(*width, *height) {
+                false
+            } else if fits(*height, *width) {
+                true
+            } else {
+                return Err(PartTooLarge {
+                    width: *width,
+                    height: *height,
+                    sheet: *sheet,
+                });
+            };
+            let (width, height) = if rotated {
+                (*height, *width)
+            } else {
+                (*width, *height)
+            };
+            Ok((index, width, height, rotated))
+        })
+        .collect::<Result<Vec<_>, PartTooLarge>>()?;
+    oriented.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+    let mut placements = vec![None; parts.len()];
+    let (mut sheet_index, mut x, mut y, mut shelf_height) = (0, 0.0, 0.0, 0.0);
+    for (index, width, height, rotated) in oriented {
+        if x > 0.0 && x + width > sheet.width {
+            // Next shelf
+            y += shelf_height + clearance;
+            x = 0.0;
+            shelf_height = 0.0;
+        }
+        if y + height > sheet.height {
+            sheet_index += 1;
+            x = 0.0;
+            y = 0.0;
+            shelf_height = 0.0;
+        }
+
+        placements[index] = Some(Placement {
+            sheet: sheet_index,
+            x,
+            y,
+            rotated,
+        });
+        x += width + clearance;
+        shelf_height = f32::max(shelf_height, height);
+    }
+
+    Ok(placements.into_iter().flatten().collect())
+}
+
+/// Number of sheets needed for the parts in the bill of materials
+pub fn sheet_count(items: &[BomItem], sheet: &SheetSize) -> Result<usize, PartTooLarge> {
+    let parts = items
+        .iter()
+        .flat_map(|item| vec![(item.width, item.length); item.quantity])
+        .collect::<Vec<(f32, f32)>>();
+
+    Ok(pack(&parts, sheet)?
+        .iter()
+        .map(|placement| placement.sheet + 1)
+        .max()
+        .unwrap_or_default())
+}
+
+/// Place parts on sheets, returns one SVG document per sheet. Engraved text moves with its part.
+pub fn nest_parts(parts: &[Part], sheet: &SheetSize) -> Result<Vec<Document>, PartTooLarge> {
+    let sizes = parts
+        .iter()
+        .map(|part| {
+            let (min_x, min_y, max_x, max_y) = part.bounding_box();
+            (max_x - min_x, max_y - min_y)
+        })
+        .collect::<Vec<(f32, f32)>>();
+    let placements = pack(&sizes, sheet)?;
+
+    let sheets = placements
+        .iter()
+        .map(|placement| placement.sheet + 1)
+        .max()
+        .unwrap_or_default();
+    let mut documents = (0..sheets)
+        .map(|_| {
+            Document::new()
+                .set("viewBox", (0, 0, sheet.width, sheet.height))
+                .set("width", format!("{}mm", sheet.width))
+                .set("height", format!("{}mm", sheet.height))
+        })
+        .collect::<Vec<Document>>();
+
+    for (part, placement) in parts.iter().zip(placements) {
+        let placed = part.place(placement.x, placement.y, placement.rotated);
+        for contour in placed.contours() {
+            documents[placement.sheet].append(contour_path(contour));
+        }
+        for engraving in placed.engravings {
+            documents[placement.sheet].append(engraving.element);
+        }
+    }
+
+    Ok(documents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rack::geometry::{document_contours, document_parts, Contour};
+
+    fn rectangle(width: f32, height: f32) -> Part {
+        Part::new(
+            Contour {
+                points: vec![(0.0, 0.0), (width, 0.0), (width, height), (0.0, height)],
+                closed: true,
+                color: "black".to_string(),
+            },
+            vec![],
+        )
+    }
+
+    #[test]
+    fn nest_parts_starts_new_sheet_when_full() {
+        let sheet = SheetSize {
+            width: 100.0,
+            height: 100.0,
+        };
+        let parts = vec![rectangle(90.0, 60.0); 3];
+
+        let sheets = nest_parts(&parts, &sheet).unwrap();
+
+        assert_eq!(sheets.len(), 3);
+        for document in &sheets {
+            assert_eq!(document_contours(document).len(), 1);
+        }
+    }
+
+    #[test]
+    fn nest_parts_keeps_parts_inside_sheet() {
+        let sheet = SheetSize::default();
+        let parts = vec![rectangle(50.0, 300.0), rectangle(200.0, 40.0)];
+
+        let sheets = nest_parts(&parts, &sheet).unwrap();
+
+        assert_eq!(sheets.len(), 1);
+        for contour in document_contours(&sheets[0]) {
+            for (x, y) in contour.points {
+                assert!((0.0..=sheet.width).contains(&x));
+                assert!((0.0..=sheet.height).contains(&y));
+            }
+        }
+    }
+
+    #[test]
+    fn nest_parts_rejects_part_larger_than_sheet() {
+        let sheet = SheetSize::default();
+
+        let error = nest_parts(&[rectangle(700.0, 500.0)], &sheet).unwrap_err();
+
+        assert_eq!(
+            error,
+            PartTooLarge {
+                width: 700.0,
+                height: 500.0,
+                sheet
+            }
+        );
+    }
+
+    #[test]
+    fn nest_parts_turns_text_with_its_part() {
+        let document = Document::new()
+            .add(contour_path(&rectangle(50.0, 300.0).outline))
+            .add(
+                svg::node::element::Text::new("A1")
+                    .set("x", 25)
+                    .set("y", 150),
+            )
+            .add(
+                svg::node::element::Text::new("off")
+                    .set("x", 80)
+                    .set("y", 150),
+            );
+        let parts = document_parts(&document);
+        assert_eq!(parts[0].engravings.len(), 1);
+
+        let sheets = nest_parts(&parts, &SheetSize::default()).unwrap();
+
+        let text = sheets[0].to_string();
+        assert!(text.contains(r#"transform="rotate(-90 150 25)" x="150" y="25""#));
+        assert!(text.contains(">A1</text>"));
+        assert!(!text.contains("off"));
+    }
+}
@@ -0,0 +1,389 @@
+//! Reliefs for inside corners, needed when the parts are cut with a round router bit.
+//!
+//! Bit leaves a rounded corner where the slot needs a sharp one, so the tab doesn't go in.
+//! Dogbone cuts a circle along the bisector of the corner, T-bone along one of its edges.
+//! Arcs are written as short lines, so the rest of the geometry keeps working on polygons.
+
+use std::f32::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+
+use svg::node::element::path::Data;
+use svg::{Document, Node};
+
+use super::geometry::{
+    contour_data, contour_path, data_contours, distance, document_parts, empty_copy, Contour, Point,
+};
+
+/// Points replacing the corner and how much of the previous and next edge they take
+type Relief = (Vec<Point>, (f32, f32));
+
+/// Shape of the relief at inside corners
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub enum ReliefStyle {
+    /// Circle along the bisector of the corner, cuts a little into both edges
+    Dogbone,
+    /// Circle along the longer edge, the shorter edge stays straight
+    TBone,
+}
+
+impl FromStr for ReliefStyle {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "dogbone" => Ok(ReliefStyle::Dogbone),
+            "t-bone" | "tbone" => Ok(ReliefStyle::TBone),
+            _ => Err(format!("Unknown relief '{}', use dogbone or t-bone", value)),
+        }
+    }
+}
+
+impl fmt::Display for ReliefStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReliefStyle::Dogbone => write!(f, "dogbone"),
+            ReliefStyle::TBone => write!(f, "t-bone"),
+        }
+    }
+}
+
+impl TryFrom<String> for ReliefStyle {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<ReliefStyle> for String {
+    fn from(style: ReliefStyle) -> Self {
+        style.to_string()
+    }
+}
+
+/// Add reliefs to every corner of every hole in the document, e.g. slots for the tabs
+pub fn relieve_holes(document: &Document, style: ReliefStyle, tool_diameter: f32) -> Document {
+    let mut relieved = empty_copy(document);
+    for part in document_parts(document) {
+        for hole in &part.holes {
+            relieved.append(contour_path(&relieve_contour(
+                hole,
+                style,
+                tool_diameter,
+                true,
+            )));
+        }
+        relieved.append(contour_path(&part.outline));
+        for contour in &part.joined {
+            relieved.append(contour_path(contour));
+        }
+    }
+    relieved
+}
+
+/// Number of corners of the holes in the document where no relief fits, they would be left
+/// sharp by `relieve_holes`
+pub fn unrelieved_hole_corners(
+    document: &Document,
+    style: ReliefStyle,
+    tool_diameter: f32,
+) -> usize {
+    document_parts(document)
+        .iter()
+        .flat_map(|part| &part.holes)
+        .map(|hole| unrelieved_corners(hole, style, tool_diameter, true))
+        .sum()
+}
+
+/// Path data with reliefs at the inside corners, see `relieve_contour`
+pub fn relieve_data(data: &Data, style: ReliefStyle, tool_diameter: f32, hole: bool) -> Data {
+    data_contours(data, "")
+        .iter()
+        .map(|contour| contour_data(&relieve_contour(contour, style, tool_diameter, hole)))
+        .fold(Data::new(), |mut relieved, data| {
+            for command in data.iter() {
+                relieved.append(command.clone());
+            }
+            relieved
+        })
+}
+
+/// Add reliefs to the inside corners of the contour.
+///
+/// Inside corners of a hole are its convex corners, of an outline its concave corners.
+/// Dogbone that doesn't fit on the edges falls back to a T-bone along one of them, corners
+/// where no relief fits are left as they are, see `unrelieved_corners`.
+pub fn relieve_contour(
+    contour: &Contour,
+    style: ReliefStyle,
+    tool_diameter: f32,
+    hole: bool,
+) -> Contour {
+    relieve(contour, style, tool_diameter, hole).0
+}
+
+/// Number of inside corners of the contour where no relief fits on the edges
+pub fn unrelieved_corners(
+    contour: &Contour,
+    style: ReliefStyle,
+    tool_diameter: f32,
+    hole: bool,
+) -> usize {
+    relieve(contour, style, tool_diameter, hole).1
+}
+
+fn relieve(
+    contour: &Contour,
+    style: ReliefStyle,
+    tool_diameter: f32,
+    hole: bool,
+) -> (Contour, usize) {
+    let points = &contour.points;
+    let count = points.len();
+    if count < 3 || tool_diameter <= 0.0 {
+        return (contour.clone(), 0);
+    }
+    let radius = tool_diameter / 2.0;
+    let signed_area: f32 = (0..count)
+        .map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % count]);
+            a.0 * b.1 - b.0 * a.1
+        })
+        .sum();
+
+    // Reliefs that may replace the corner, preferred one first
+    let candidates = (0..count)
+        .map(|index| {
+            let previous = points[(index + count - 1) % count];
+            let corner = points[index];
+            let next = points[(index + 1) % count];
+            let is_end = !contour.closed && (index == 0 || index == count - 1);
+            let (d1, d2) = (unit(previous, corner), unit(corner, next));
+            let turn = d1.0 * d2.1 - d1.1 * d2.0;
+            let convex = turn * signed_area > 0.0;
+
+            // Nearly straight corners, e.g. of circles drawn as polygons, don't need a relief
+            if is_end || turn.abs() < 0.5 || convex != hole {
+                return vec![];
+            }
+            let longer_previous = distance(&previous, &corner) >= distance(&corner, &next);
+            let t_bones = [longer_previous, !longer_previous]
+                .into_iter()
+                .filter_map(|along_previous| t_bone(corner, d1, d2, radius, along_previous));
+            match style {
+                ReliefStyle::Dogbone => dogbone(corner, d1, d2, radius)
+                    .into_iter()
+                    .chain(t_bones)
+                    .collect(),
+                ReliefStyle::TBone => t_bones.collect(),
+            }
+        })
+        .collect::<Vec<Vec<Relief>>>();
+    let mut reliefs = candidates
+        .iter()
+        .map(|candidates| candidates.first())
+        .collect::<Vec<Option<&Relief>>>();
+
+    // Edge must have room for the reliefs at both of its ends
+    let fits = |reliefs: &[Option<&Relief>], index: usize| {
+        let taken = |index: usize| {
+            reliefs[index]
+                .map(|(_, lengths)| *lengths)
+                .unwrap_or_default()
+        };
+        let (previous, next) = ((index + count - 1) % count, (index + 1) % count);
+        taken(previous).1 + taken(index).0 <= distance(&points[previous], &points[index])
+            && taken(index).1 + taken(next).0 <= distance(&points[index], &points[next])
+    };
+    // Reliefs that don't fit make room for the others to fall back to
+    let misfits = (0..count)
+        .filter(|index| reliefs[*index].is_some() && !fits(&reliefs, *index))
+        .collect::<Vec<usize>>();
+    for index in &misfits {
+        reliefs[*index] = None;
+    }
+    for index in misfits {
+        reliefs[index] = candidates[index].iter().find(|relief| {
+            let mut tried = reliefs.clone();
+            tried[index] = Some(relief);
+            fits(&tried, index)
+        });
+    }
+
+    let mut relieved = vec![];
+    for (index, relief) in reliefs.iter().enumerate() {
+        match relief {
+            Some((arc, _)) => relieved.extend(arc),
+            None => relieved.push(points[index]),
+        }
+    }
+    let unrelieved = (0..count)
+        .filter(|index| !candidates[*index].is_empty() && reliefs[*index].is_none())
+        .count();
+
+    (
+        Contour {
+            points: relieved,
+            closed: contour.closed,
+            color: contour.color.clone(),
+        },
+        unrelieved,
+    )
+}
+
+/// Circle through the corner with center on the bisector, returns the arc replacing the
+/// corner and how much of the previous and next edge it takes
+fn dogbone(corner: Point, d1: Point, d2: Point, radius: f32) -> Option<Relief> {
+    let bisector = unit((0.0, 0.0), (d2.0 - d1.0, d2.1 - d1.1));
+    let center = (
+        corner.0 + radius * bisector.0,
+        corner.1 + radius * bisector.1,
+    );
+    let along = -2.0 * radius * (d1.0 * bisector.0 + d1.1 * bisector.1);
+    let start = (corner.0 - along * d1.0, corner.1 - along * d1.1);
+    let end = (corner.0 + along * d2.0, corner.1 + along * d2.1);
+
+    Some((arc(center, radius, start, corner, end), (along, along)))
+}
+
+/// Circle through the corner with center on the longer edge, only for square corners
+fn t_bone(
+    corner: Point,
+    d1: Point,
+    d2: Point,
+    radius: f32,
+    along_previous: bool,
+) -> Option<Relief> {
+    if (d1.0 * d2.0 + d1.1 * d2.1).abs() > 0.01 {
+        return None;
+    }
+
+    if along_previous {
+        let center = (corner.0 - radius * d1.0, corner.1 - radius * d1.1);
+        let start = (
+            corner.0 - 2.0 * radius * d1.0,
+            corner.1 - 2.0 * radius * d1.1,
+        );
+        let middle = (center.0 - radius * d2.0, center.1 - radius * d2.1);
+        Some((
+            arc(center, radius, start, middle, corner),
+            (2.0 * radius, 0.0),
+        ))
+    } else {
+        let center = (corner.0 + radius * d2.0, corner.1 + radius * d2.1);
+        let end = (
+            corner.0 + 2.0 * radius * d2.0,
+            corner.1 + 2.0 * radius * d2.1,
+        );
+        let middle = (center.0 + radius * d1.0, center.1 + radius * d1.1);
+        Some((
+            arc(center, radius, corner, middle, end),
+            (0.0, 2.0 * radius),
+        ))
+    }
+}
+
+/// Points of the arc from `start` through `via` to `end`, about 15 degrees apart
+fn arc(center: Point, radius: f32, start: Point, via: Point, end: Point) -> Vec<Point> {
+    let angle = |(x, y): Point| (y - center.1).atan2(x - center.0);
+    let counter_clockwise = |from: f32, to: f32| (to - from).rem_euclid(2.0 * PI);
+    let (a0, am, a1) = (angle(start), angle(via), angle(end));
+
+    let sweep = if counter_clockwise(a0, am) <= counter_clockwise(a0, a1) {
+        counter_clockwise(a0, a1)
+    } else {
+        counter_clockwise(a0, a1) - 2.0 * PI
+    };
+    let steps = (sweep.abs() / (PI / 12.0)).ceil().max(1.0) as usize;
+
+    let mut points = vec![start];
+    points.extend((1..steps).map(|step| {
+        let angle = a0 + sweep * step as f32 / steps as f32;
+        (
+            center.0 + radius * angle.cos(),
+            center.1 + radius * angle.sin(),
+        )
+    }));
+    points.push(end);
+    points
+}
+
+fn unit(from: Point, to: Point) -> Point {
+    let length = distance(&from, &to);
+    if length == 0.0 {
+        (0.0, 0.0)
+    } else {
+        ((to.0 - from.0) / length, (to.1 - from.1) / length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rack::geometry::bounding_box;
+
+    fn slot(width: f32, length: f32) -> Contour {
+        Contour {
+            points: vec![(0.0, 0.0), (width, 0.0), (width, length), (0.0, length)],
+            closed: true,
+            color: "black".to_string(),
+        }
+    }
+
+    #[test]
+    fn dogbone_cuts_into_both_edges_of_wide_slot() {
+        let relieved = relieve_contour(&slot(20.0, 20.0), ReliefStyle::Dogbone, 3.175, true);
+
+        let (min_x, min_y, max_x, max_y) = bounding_box(&relieved.points);
+        assert!(min_x < 0.0 && min_y < 0.0 && max_x > 20.0 && max_y > 20.0);
+        assert_eq!(
+            unrelieved_corners(&slot(20.0, 20.0), ReliefStyle::Dogbone, 3.175, true),
+            0
+        );
+    }
+
+    #[test]
+    fn dogbone_falls_back_to_t_bone_in_narrow_slot() {
+        let relieved = relieve_contour(&slot(4.0, 20.0), ReliefStyle::Dogbone, 3.175, true);
+
+        // Short edge has room for one dogbone, the other corner gets a T-bone along the
+        // long edge
+        let (min_x, _, max_x, _) = bounding_box(&relieved.points);
+        assert_eq!((min_x, max_x), (-1.5875, 5.5875));
+        assert_eq!(
+            unrelieved_corners(&slot(4.0, 20.0), ReliefStyle::Dogbone, 3.175, true),
+            0
+        );
+    }
+
+    #[test]
+    fn corners_without_room_are_left_and_counted() {
+        let slot = slot(4.0, 4.0);
+
+        assert_eq!(relieve_contour(&slot, ReliefStyle::TBone, 6.0, true), slot);
+        assert_eq!(
+            unrelieved_corners(&slot, ReliefStyle::Dogbone, 6.0, true),
+            4
+        );
+    }
+
+    #[test]
+    fn outline_of_convex_part_needs_no_relief() {
+        let outline = slot(20.0, 20.0);
+
+        assert_eq!(
+            relieve_contour(&outline, ReliefStyle::Dogbone, 3.175, false),
+            outline
+        );
+        assert_eq!(
+            unrelieved_corners(&outline, ReliefStyle::Dogbone, 3.175, false),
+            0
+        );
+    }
+}
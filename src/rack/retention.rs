@@ -0,0 +1,221 @@
+//! Retention bars for racks in vehicles.
+//!
+//! Bar lies across the front of a row, in front of the lower part of the containers where
+//! they are narrower. It drops into slots cut into the front edge of the side panels, a finger
+//! of the panel holds it in front, and is lifted out to take the containers out.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::geometry::Point;
+use super::{top_width, Dimensions, CLEARANCE_BETWEEN_PATHS};
+
+/// How the retention bars are split
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub enum RetentionBars {
+    /// One bar across the full width of every row
+    Rows,
+    /// Bar for every slot, neighbouring bars meet in the middle of the side panel
+    Columns,
+}
+
+/// Height of the bar in front of the row, a third of the container
+pub fn bar_height(row: &Dimensions) -> f32 {
+    (row.height / 3) as f32
+}
+
+/// How far the slot goes into the side panel from its front edge
+pub fn slot_depth(material_thickness: f32, fit_clearance: f32) -> f32 {
+    2.0 * material_thickness + fit_clearance
+}
+
+/// Slot in the front edge at `front_y` for the bar of a row ending at `row_bottom_x`, the panel
+/// is at larger `y`. Points go from the bottom of the row up, the way the outline of the side
+/// panel goes.
+///
+/// Bar goes into the top of the slot and drops into the channel behind the finger, the
+/// channel is as wide as the bar with the clearance.
+pub fn slot(
+    row_bottom_x: f32,
+    front_y: f32,
+    bar_height: f32,
+    material_thickness: f32,
+    fit_clearance: f32,
+) -> Vec<Point> {
+    let bar_bottom = row_bottom_x - material_thickness;
+    let finger_top = bar_bottom - bar_height / 2.0;
+    let top = finger_top - bar_height;
+    let finger = front_y + material_thickness;
+    let back = front_y + slot_depth(material_thickness, fit_clearance);
+    vec![
+        (finger_top, front_y),
+        (finger_top, finger),
+        (bar_bottom, finger),
+        (bar_bottom, back),
+        (top, back),
+        (top, front_y),
+    ]
+}
+
+/// Length and height of every bar, row by row from the top
+pub fn bar_sizes(
+    bars: RetentionBars,
+    rows: &[Dimensions],
+    column_pitches: &[f32],
+    material_thickness: f32,
+) -> Vec<(f32, f32)> {
+    let width = top_width(column_pitches, material_thickness);
+    // Bars of the columns end in the middle of the panels, outer ones at the outer faces
+    let mut ends = vec![0.0];
+    let mut x = 0.0;
+    for pitch in &column_pitches[..column_pitches.len().saturating_sub(1)] {
+        x += pitch;
+        ends.push(x + material_thickness / 2.0);
+    }
+    ends.push(width);
+
+    rows.iter()
+        .flat_map(|row| {
+            let height = bar_height(row);
+            match bars {
+                RetentionBars::Rows => vec![(width, height)],
+                RetentionBars::Columns => ends
+                    .windows(2)
+                    .map(|end| (end[1] - end[0], height))
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// Positions of the bars laid out in lines of `width` and the height they take on the sheet
+pub fn bar_positions(sizes: &[(f32, f32)], width: f32) -> (Vec<Point>, f32) {
+    let clearance = CLEARANCE_BETWEEN_PATHS as f32;
+    let (mut x, mut y, mut line_height) = (0.0, 0.0, 0.0_f32);
+    let mut positions = vec![];
+    for (length, height) in sizes {
+        if x > 0.0 && x + length > width {
+            y += line_height + clearance;
+            (x, line_height) = (0.0, 0.0);
+        }
+        positions.push((x, y));
+        x += length + clearance;
+        line_height = line_height.max(*height);
+    }
+    let height = if positions.is_empty() {
+        0.0
+    } else {
+        y + line_height + clearance
+    };
+    (positions, height)
+}
+
+impl FromStr for RetentionBars {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "rows" => Ok(RetentionBars::Rows),
+            "columns" => Ok(RetentionBars::Columns),
+            _ => Err(format!(
+                "Unknown retention bars '{}', use rows or columns",
+                value
+            )),
+        }
+    }
+}
+
+impl fmt::Display for RetentionBars {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RetentionBars::Rows => write!(f, "rows"),
+            RetentionBars::Columns => write!(f, "columns"),
+        }
+    }
+}
+
+impl TryFrom<String> for RetentionBars {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<RetentionBars> for String {
+    fn from(bars: RetentionBars) -> Self {
+        bars.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(height: usize) -> Dimensions {
+        Dimensions {
+            width: 170,
+            depth: 210,
+            height,
+            side_wing_from_box_top: 15,
+            side_wing_width: 8,
+        }
+    }
+
+    #[test]
+    fn row_bars_span_the_rack_and_column_bars_meet_in_the_panels() {
+        let rows = [row(60), row(120)];
+        let pitches = [174.0, 104.0];
+
+        let sizes = bar_sizes(RetentionBars::Rows, &rows, &pitches, 4.0);
+        assert_eq!(sizes, vec![(286.0, 20.0), (286.0, 40.0)]);
+
+        // Outer bars end on the outer faces, inner ends in the middle of the inner panel
+        let sizes = bar_sizes(RetentionBars::Columns, &rows[..1], &pitches, 4.0);
+        assert_eq!(sizes, vec![(176.0, 20.0), (110.0, 20.0)]);
+    }
+
+    #[test]
+    fn bars_wrap_to_the_next_line() {
+        let clearance = CLEARANCE_BETWEEN_PATHS as f32;
+        let (positions, height) =
+            bar_positions(&[(100.0, 20.0), (100.0, 30.0), (150.0, 10.0)], 250.0);
+        assert_eq!(
+            positions,
+            vec![
+                (0.0, 0.0),
+                (100.0 + clearance, 0.0),
+                (0.0, 30.0 + clearance)
+            ]
+        );
+        assert_eq!(height, 30.0 + 10.0 + 2.0 * clearance);
+
+        assert_eq!(bar_positions(&[], 250.0), (vec![], 0.0));
+    }
+
+    #[test]
+    fn slot_holds_the_bar_behind_the_finger() {
+        let slot = slot(100.0, 0.0, 18.0, 4.0, 0.2);
+        assert_eq!(
+            slot,
+            vec![
+                (87.0, 0.0),
+                (87.0, 4.0),
+                (96.0, 4.0),
+                (96.0, 8.2),
+                (69.0, 8.2),
+                (69.0, 0.0)
+            ]
+        );
+        // Channel takes the bar with the clearance, opening above the finger the whole bar
+        let channel = slot[3].1 - slot[2].1;
+        assert!((channel - 4.2).abs() < 1e-5);
+        assert_eq!(slot[0].0 - slot[5].0, 18.0);
+        assert_eq!(slot_depth(4.0, 0.2), slot[3].1);
+    }
+}
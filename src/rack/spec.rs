@@ -0,0 +1,471 @@
+use std::fmt;
+use std::str::FromStr;
+
+use svg::Document;
+
+use super::material::Material;
+use super::{
+    generate_layout_svg, Container, Dimensions, Layout, LayoutError, RackOptions, MAX_COLUMNS,
+    MAX_ROWS,
+};
+
+/// Height of a single row in the rack
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub enum RowSpec {
+    /// Multiple of the container height, e.g. 2 fits container twice as tall
+    Multiple(usize),
+    /// Exact height of the row in mm
+    Height(usize),
+    /// Key of the container in the row
+    Container(String),
+}
+
+impl FromStr for RowSpec {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let (number, spec): (&str, fn(usize) -> RowSpec) = match value.strip_suffix("mm") {
+            Some(height) => (height, RowSpec::Height),
+            None => (value, RowSpec::Multiple),
+        };
+
+        if !number.trim().starts_with(|c: char| c.is_ascii_digit()) {
+            return Ok(RowSpec::Container(value.to_string()));
+        }
+
+        match number.trim().parse::<usize>() {
+            Ok(0) | Err(_) => Err(format!(
+                "Invalid row '{}', expected positive number like `2`, `120mm` or container key",
+                value
+            )),
+            Ok(number) => Ok(spec(number)),
+        }
+    }
+}
+
+impl TryFrom<String> for RowSpec {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<RowSpec> for String {
+    fn from(row: RowSpec) -> Self {
+        row.to_string()
+    }
+}
+
+impl fmt::Display for RowSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RowSpec::Multiple(multiple) => write!(f, "{}", multiple),
+            RowSpec::Height(height) => write!(f, "{}mm", height),
+            RowSpec::Container(key) => write!(f, "{}", key),
+        }
+    }
+}
+
+impl RowSpec {
+    /// Dimensions of the row, `container` is the main container of the rack
+    pub fn to_dimensions(
+        &self,
+        container: &Container,
+        supported_containers: &[Container],
+    ) -> Result<Dimensions, SpecError> {
+        let height = match self {
+            RowSpec::Multiple(multiple) => container
+                .dimensions
+                .height
+                .checked_mul(*multiple)
+                .ok_or_else(|| SpecError::RowTooTall(self.clone()))?,
+            RowSpec::Height(height) => *height,
+            RowSpec::Container(key) => {
+                return find_container(key, supported_containers).map(|c| c.dimensions.clone())
+            }
+        };
+
+        Ok(Dimensions {
+            height,
+            ..container.dimensions.clone()
+        })
+    }
+}
+
+/// Everything needed to generate one rack, same options as the `generate` command has
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct RackSpec {
+    /// Key of container
+    pub container: String,
+    /// Number of rows, used when `rows_spec` is empty
+    pub rows: usize,
+    /// Height of each row from top to bottom
+    pub rows_spec: Vec<RowSpec>,
+    /// Number of columns, used when `column_widths` is empty
+    pub columns: usize,
+    /// Width of the container in each column from left to right in mm
+    pub column_widths: Vec<usize>,
+    /// Thickness of the material, used when `material` is empty
+    pub material_thickness: f32,
+    /// Name of the material preset
+    pub material: String,
+    pub primary_color: String,
+    pub secondary_color: String,
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub options: RackOptions,
+}
+
+impl Default for RackSpec {
+    fn default() -> Self {
+        RackSpec {
+            container: String::new(),
+            rows: 0,
+            rows_spec: vec![],
+            columns: 0,
+            column_widths: vec![],
+            material_thickness: 0.0,
+            material: String::new(),
+            primary_color: "black".to_string(),
+            secondary_color: "blue".to_string(),
+            options: RackOptions::default(),
+        }
+    }
+}
+
+impl RackSpec {
+    pub fn rows_spec(&self) -> Vec<RowSpec> {
+        if self.rows_spec.is_empty() {
+            vec![RowSpec::Multiple(1); self.rows]
+        } else {
+            self.rows_spec.clone()
+        }
+    }
+
+    pub fn columns(&self) -> usize {
+        if self.column_widths.is_empty() {
+            self.columns
+        } else {
+            self.column_widths.len()
+        }
+    }
+
+    /// Set field from text, e.g. form field or CSV column. Lists are separated by commas
+    pub fn set(&mut self, field: &str, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        match field {
+            "container" => self.container = value.to_string(),
+            "rows" => self.rows = parse(field, value)?,
+            "rows_spec" => self.rows_spec = parse_list(field, value)?,
+            "columns" => self.columns = parse(field, value)?,
+            "column_widths" => self.column_widths = parse_list(field, value)?,
+            "material_thickness" => self.material_thickness = parse(field, value)?,
+            "material" => self.material = value.to_string(),
+            "primary_color" => self.primary_color = value.to_string(),
+            "secondary_color" => self.secondary_color = value.to_string(),
+            "common_line" => self.options.common_line = parse(field, value)?,
+            "relief" => self.options.relief = Some(parse(field, value)?),
+            "tool_diameter" => self.options.tool_diameter = parse(field, value)?,
+            "corner_joint" => self.options.corner_joint = parse(field, value)?,
+            "screw" => self.options.screw = parse(field, value)?,
+            "wall_mount" => self.options.wall_mount = Some(parse(field, value)?),
+            "stacking" => self.options.stacking = parse(field, value)?,
+            "engrave_color" => self.options.engrave_color = value.to_string(),
+            "drawer_stop" => self.options.drawer_stop = Some(parse(field, value)?),
+            "drawer_stop_depth" => self.options.drawer_stop_depth = parse(field, value)?,
+            "retention_bars" => self.options.retention_bars = Some(parse(field, value)?),
+            _ => return Err(format!("Unknown field '{}'", field)),
+        }
+
+        Ok(())
+    }
+
+    pub fn container<'a>(
+        &self,
+        supported_containers: &'a [Container],
+    ) -> Result<&'a Container, SpecError> {
+        find_container(&self.container, supported_containers)
+    }
+
+    /// Material preset by name, or material of given thickness when no name is given
+    pub fn material(&self, materials: &[Material]) -> Result<Material, SpecError> {
+        if self.material.is_empty() {
+            if self.material_thickness <= 0.0 {
+                return Err(SpecError::InvalidMaterialThickness(self.material_thickness));
+            }
+            return Ok(Material::from(self.material_thickness));
+        }
+
+        materials
+            .iter()
+            .find(|material| material.name == self.material)
+            .cloned()
+            .ok_or(SpecError::UnknownMaterial(self.material.clone()))
+    }
+
+    /// Resolve rows and columns and check that the rack can be built
+    pub fn layout(&self, supported_containers: &[Container]) -> Result<Layout, SpecError> {
+        // Counts are checked before the rows are built, spec may come from a web form
+        let rows = if self.rows_spec.is_empty() {
+            self.rows
+        } else {
+            self.rows_spec.len()
+        };
+        if rows > MAX_ROWS {
+            return Err(LayoutError::TooManyRows(rows).into());
+        }
+        if self.columns() > MAX_COLUMNS {
+            return Err(LayoutError::TooManyColumns(self.columns()).into());
+        }
+
+        let container = self.container(supported_containers)?;
+        let rows = self
+            .rows_spec()
+            .iter()
+            .map(|row| row.to_dimensions(container, supported_containers))
+            .collect::<Result<Vec<Dimensions>, SpecError>>()?;
+
+        let mut layout = Layout::with_rows(rows, self.columns());
+        if !self.column_widths.is_empty() {
+            layout.column_widths = self.column_widths.clone();
+        }
+        layout.options = self.options.clone();
+        layout.validate()?;
+
+        Ok(layout)
+    }
+
+    /// Generate SVG document for the rack
+    pub fn generate(
+        &self,
+        supported_containers: &[Container],
+        materials: &[Material],
+    ) -> Result<Document, SpecError> {
+        let material = self.material(materials)?;
+        let layout = self.layout(supported_containers)?;
+
+        Ok(generate_layout_svg(
+            &layout,
+            material,
+            &self.primary_color,
+            &self.secondary_color,
+        )?)
+    }
+
+    /// Name of the file without extension, describes rows, columns, thickness and container
+    pub fn default_filename(&self) -> String {
+        let rows = if self.rows_spec.is_empty() {
+            self.rows.to_string()
+        } else {
+            join(&self.rows_spec)
+        };
+        let columns = if self.column_widths.is_empty() {
+            self.columns.to_string()
+        } else {
+            join(&self.column_widths)
+        };
+
+        let material = if self.material.is_empty() {
+            format!("{}mm_thick", self.material_thickness)
+        } else {
+            self.material.clone()
+        };
+
+        format!(
+            "organizer_{}_rows_{}_columns_{}_{}",
+            rows, columns, material, self.container
+        )
+    }
+}
+
+fn parse<T: FromStr>(field: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value '{}' for {}", value, field))
+}
+
+fn parse_list<T: FromStr>(field: &str, value: &str) -> Result<Vec<T>, String> {
+    value
+        .split(',')
+        .filter(|item| !item.trim().is_empty())
+        .map(|item| parse(field, item.trim()))
+        .collect()
+}
+
+fn join<T: ToString>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<String>>()
+        .join("-")
+}
+
+fn find_container<'a>(
+    key: &str,
+    supported_containers: &'a [Container],
+) -> Result<&'a Container, SpecError> {
+    supported_containers
+        .iter()
+        .find(|c| c.key() == key)
+        .ok_or(SpecError::UnknownContainer(key.to_string()))
+}
+
+/// Reason why rack can't be generated from the spec
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpecError {
+    UnknownContainer(String),
+    UnknownMaterial(String),
+    InvalidMaterialThickness(f32),
+    /// Multiple of the container height doesn't fit in a number
+    RowTooTall(RowSpec),
+    Layout(LayoutError),
+}
+
+impl fmt::Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpecError::UnknownContainer(key) => {
+                write!(f, "No supported container found for '{}'", key)
+            }
+            SpecError::UnknownMaterial(name) => {
+                write!(f, "No material found for '{}'", name)
+            }
+            SpecError::InvalidMaterialThickness(thickness) => {
+                write!(f, "Material thickness must be positive, got {}", thickness)
+            }
+            SpecError::RowTooTall(row) => write!(f, "Row '{}' is too tall", row),
+            SpecError::Layout(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for SpecError {}
+
+impl From<LayoutError> for SpecError {
+    fn from(error: LayoutError) -> Self {
+        SpecError::Layout(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rack::stop::DrawerStop;
+
+    #[test]
+    fn row_spec_parses_multiple_height_and_container() {
+        assert_eq!("2".parse(), Ok(RowSpec::Multiple(2)));
+        assert_eq!(" 120mm ".parse(), Ok(RowSpec::Height(120)));
+        assert_eq!(
+            "smartstore-classic_3".parse(),
+            Ok(RowSpec::Container("smartstore-classic_3".to_string()))
+        );
+    }
+
+    #[test]
+    fn row_spec_rejects_zero_and_garbage_numbers() {
+        assert!("0".parse::<RowSpec>().is_err());
+        assert!("0mm".parse::<RowSpec>().is_err());
+        assert!("12x".parse::<RowSpec>().is_err());
+    }
+
+    #[test]
+    fn row_spec_round_trips_through_display() {
+        for row in ["3", "56mm", "smartstore-home_1"] {
+            assert_eq!(row.parse::<RowSpec>().unwrap().to_string(), row);
+        }
+    }
+
+    fn spec() -> RackSpec {
+        RackSpec {
+            container: "smartstore-classic_1".to_string(),
+            rows: 2,
+            columns: 2,
+            material_thickness: 4.0,
+            ..RackSpec::default()
+        }
+    }
+
+    #[test]
+    fn set_parses_fields_and_lists() {
+        let mut spec = spec();
+        spec.set("rows", " 3 ").unwrap();
+        spec.set("column_widths", "120, 130,").unwrap();
+        spec.set("common_line", "true").unwrap();
+        spec.set("drawer_stop", "notch").unwrap();
+        assert_eq!(spec.rows, 3);
+        assert_eq!(spec.column_widths, vec![120, 130]);
+        assert!(spec.options.common_line);
+        assert_eq!(spec.options.drawer_stop, Some(DrawerStop::Notch));
+    }
+
+    #[test]
+    fn set_rejects_unknown_field_and_invalid_value() {
+        let mut spec = spec();
+        assert_eq!(
+            spec.set("colour", "red").unwrap_err(),
+            "Unknown field 'colour'"
+        );
+        assert!(spec.set("rows", "many").is_err());
+        assert_eq!(spec.rows, 2);
+    }
+
+    #[test]
+    fn layout_rejects_huge_row_multiple_without_overflow() {
+        let spec = RackSpec {
+            rows_spec: vec!["999999999999999999".parse().unwrap()],
+            ..spec()
+        };
+        assert_eq!(
+            spec.layout(&crate::supported_containers()).unwrap_err(),
+            SpecError::RowTooTall(RowSpec::Multiple(999999999999999999))
+        );
+    }
+
+    #[test]
+    fn layout_rejects_too_many_rows_and_columns() {
+        let containers = crate::supported_containers();
+        let rows = RackSpec {
+            rows: 10_000_000,
+            ..spec()
+        };
+        assert_eq!(
+            rows.layout(&containers).unwrap_err(),
+            SpecError::Layout(LayoutError::TooManyRows(10_000_000))
+        );
+        let columns = RackSpec {
+            columns: MAX_COLUMNS + 1,
+            ..spec()
+        };
+        assert_eq!(
+            columns.layout(&containers).unwrap_err(),
+            SpecError::Layout(LayoutError::TooManyColumns(MAX_COLUMNS + 1))
+        );
+    }
+
+    #[test]
+    fn layout_rejects_too_tall_row() {
+        let spec = RackSpec {
+            rows_spec: vec![RowSpec::Multiple(1), RowSpec::Height(5000)],
+            ..spec()
+        };
+        assert_eq!(
+            spec.layout(&crate::supported_containers()).unwrap_err(),
+            SpecError::Layout(LayoutError::RowTooTall {
+                row: 2,
+                height: 5000
+            })
+        );
+    }
+}
@@ -0,0 +1,128 @@
+//! Lengths and areas of the generated parts, e.g. for checking changes in geometry.
+
+use svg::node::element::Path;
+use svg::Document;
+
+use super::geometry::{bounding_box, document_parts, Contour, Part};
+
+/// Measurements of a single part in mm and mm². Pieces sharing a common line with the part
+/// are measured with it.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PartStats {
+    /// Length of the outline and the joined cuts
+    pub outer_perimeter: f32,
+    /// Total length of the holes
+    pub inner_perimeter: f32,
+    /// Area of the outline and the joined pieces without the holes
+    pub net_area: f32,
+    /// Min x, min y, max x and max y
+    pub bounding_box: (f32, f32, f32, f32),
+}
+
+impl From<&Part> for PartStats {
+    fn from(part: &Part) -> Self {
+        // Joined piece is closed by the shared line on the outline
+        let joined_area = part
+            .joined
+            .iter()
+            .map(|contour| {
+                Contour {
+                    closed: true,
+                    ..contour.clone()
+                }
+                .area()
+            })
+            .sum::<f32>();
+
+        PartStats {
+            outer_perimeter: part
+                .joined
+                .iter()
+                .fold(part.outline.length(), |length, cut| length + cut.length()),
+            inner_perimeter: part
+                .holes
+                .iter()
+                .fold(0.0, |length, hole| length + hole.length()),
+            net_area: part
+                .holes
+                .iter()
+                .fold(part.outline.area() + joined_area, |area, hole| {
+                    area - hole.area()
+                }),
+            bounding_box: part.bounding_box(),
+        }
+    }
+}
+
+/// Measurements of every part and the whole sheet
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SheetStats {
+    pub parts: Vec<PartStats>,
+    /// Sums of perimeters and areas, bounding box around all parts
+    pub total: PartStats,
+}
+
+/// Measure parts of the document, e.g. result of `generate_layout_svg`
+pub fn document_stats(document: &Document) -> SheetStats {
+    let parts = document_parts(document)
+        .iter()
+        .map(PartStats::from)
+        .collect::<Vec<PartStats>>();
+    let corners = parts
+        .iter()
+        .flat_map(|part| {
+            let (min_x, min_y, max_x, max_y) = part.bounding_box;
+            [(min_x, min_y), (max_x, max_y)]
+        })
+        .collect::<Vec<(f32, f32)>>();
+
+    let total = PartStats {
+        outer_perimeter: parts
+            .iter()
+            .fold(0.0, |sum, part| sum + part.outer_perimeter),
+        inner_perimeter: parts
+            .iter()
+            .fold(0.0, |sum, part| sum + part.inner_perimeter),
+        net_area: parts.iter().fold(0.0, |sum, part| sum + part.net_area),
+        bounding_box: if corners.is_empty() {
+            (0.0, 0.0, 0.0, 0.0)
+        } else {
+            bounding_box(&corners)
+        },
+    };
+
+    SheetStats { parts, total }
+}
+
+/// Measure parts drawn by a single path, e.g. a side wing or a side panel outline
+pub fn path_stats(path: &Path) -> Vec<PartStats> {
+    document_stats(&Document::new().add(path.clone())).parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rack::geometry::{contour_path, remove_common_lines};
+
+    fn square(x: f32, y: f32) -> Path {
+        contour_path(&Contour {
+            points: vec![(x, y), (x + 10.0, y), (x + 10.0, y + 10.0), (x, y + 10.0)],
+            closed: true,
+            color: "black".to_string(),
+        })
+    }
+
+    #[test]
+    fn common_line_pieces_are_measured_with_their_neighbour() {
+        let document = Document::new().add(square(0.0, 0.0)).add(square(0.0, 10.0));
+
+        let stats = document_stats(&remove_common_lines(&document));
+
+        assert_eq!(stats.parts.len(), 1);
+        assert_eq!(stats.total.outer_perimeter, 70.0);
+        assert_eq!(stats.total.net_area, 200.0);
+        assert_eq!(stats.total.bounding_box, (0.0, 0.0, 10.0, 20.0));
+    }
+}
@@ -0,0 +1,147 @@
+//! Drawer stops on the side wings, so containers don't slide out on an incline or in a van.
+//!
+//! Stop is near the back end of the wing, the edge with the taps going into the side panel is
+//! left as it is. Tab is a separate piece standing up in a slot of the wing, the container lip
+//! catches on it. Notch is cut into the free edge and the lip drops into it.
+
+use std::fmt;
+use std::str::FromStr;
+
+use svg::node::element::path::Data;
+
+use super::geometry::Point;
+
+/// Length of the stop along the depth of the wing
+pub const DRAWER_STOP_LENGTH: usize = 10;
+/// Distance between the stop and the back end of the wing
+pub const DRAWER_STOP_FROM_BACK: usize = 5;
+
+/// Shape of the drawer stop
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub enum DrawerStop {
+    /// Piece standing up in a slot of the wing, must stay below the top of the row
+    Tab,
+    /// Notch cut into the free edge, must leave at least half of the wing
+    Notch,
+}
+
+/// Free edge of the wing at `edge_y` from its back end at `box_depth` around the notch.
+/// `into_wing` is 1 when the wing is below the edge, -1 when above.
+pub fn notch_edge(data: Data, depth: f32, box_depth: usize, edge_y: f32, into_wing: f32) -> Data {
+    let back = box_depth - DRAWER_STOP_FROM_BACK;
+
+    data.horizontal_line_to(back)
+        .vertical_line_to(edge_y + depth * into_wing)
+        .horizontal_line_to(back - DRAWER_STOP_LENGTH)
+        .vertical_line_to(edge_y)
+}
+
+/// Slot for the tab of the stop piece in the middle of the wing between `edge_y` and `base_y`
+pub fn tab_slot(box_depth: usize, edge_y: f32, base_y: f32, slot_width: f32) -> Vec<Point> {
+    let length = DRAWER_STOP_LENGTH as f32;
+    let back = (box_depth - DRAWER_STOP_FROM_BACK) as f32 - length / 4.0;
+    let middle = (edge_y + base_y) / 2.0;
+    vec![
+        (back, middle - slot_width / 2.0),
+        (back - length / 2.0, middle - slot_width / 2.0),
+        (back - length / 2.0, middle + slot_width / 2.0),
+        (back, middle + slot_width / 2.0),
+    ]
+}
+
+/// Size of the stop piece on the sheet, it stands `depth` above the wing
+pub fn piece_size(depth: f32, material_thickness: f32) -> (f32, f32) {
+    (DRAWER_STOP_LENGTH as f32, depth + material_thickness)
+}
+
+/// Outline of the stop piece with its top left corner at `x`, `y`. Tab in the middle half of
+/// the bottom goes through the wing, the shoulders rest on it.
+pub fn piece(x: f32, y: f32, depth: f32, material_thickness: f32) -> Vec<Point> {
+    let length = DRAWER_STOP_LENGTH as f32;
+    let (shoulder, bottom) = (y + depth, y + depth + material_thickness);
+    vec![
+        (x, y),
+        (x, shoulder),
+        (x + length / 4.0, shoulder),
+        (x + length / 4.0, bottom),
+        (x + 3.0 * length / 4.0, bottom),
+        (x + 3.0 * length / 4.0, shoulder),
+        (x + length, shoulder),
+        (x + length, y),
+    ]
+}
+
+impl FromStr for DrawerStop {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "tab" => Ok(DrawerStop::Tab),
+            "notch" => Ok(DrawerStop::Notch),
+            _ => Err(format!("Unknown drawer stop '{}', use tab or notch", value)),
+        }
+    }
+}
+
+impl fmt::Display for DrawerStop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DrawerStop::Tab => write!(f, "tab"),
+            DrawerStop::Notch => write!(f, "notch"),
+        }
+    }
+}
+
+impl TryFrom<String> for DrawerStop {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<DrawerStop> for String {
+    fn from(stop: DrawerStop) -> Self {
+        stop.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tab_of_the_piece_fits_the_slot() {
+        let slot = tab_slot(210, 0.0, 8.0, 4.0);
+        let piece = piece(0.0, 0.0, 3.0, 4.0);
+        let slot_length = slot[0].0 - slot[1].0;
+        let tab_length = piece[4].0 - piece[3].0;
+        assert_eq!(slot_length, tab_length);
+        // Tab goes through the wing, the rest stands above it
+        assert_eq!(piece[3].1 - piece[2].1, 4.0);
+        assert_eq!(piece_size(3.0, 4.0), (10.0, 7.0));
+    }
+
+    #[test]
+    fn slot_is_in_the_middle_of_the_wing_near_the_back() {
+        let slot = tab_slot(210, 23.0, 15.0, 4.0);
+        assert_eq!(
+            slot,
+            vec![(202.5, 17.0), (197.5, 17.0), (197.5, 21.0), (202.5, 21.0)]
+        );
+    }
+
+    #[test]
+    fn notch_is_cut_into_the_wing() {
+        let data = notch_edge(Data::new().move_to((0.0, 0.0)), 3.0, 210, 0.0, 1.0);
+        assert_eq!(
+            svg::node::Value::from(data).to_string(),
+            "M0,0 H205 V3 H195 V0"
+        );
+    }
+}
@@ -0,0 +1,181 @@
+//! Order of the cuts. Laser moves from the end of one cut to the start of the next one
+//! without cutting, shorter moves make the job faster.
+
+use svg::{Document, Node};
+
+use super::geometry::{contour_path, distance, document_contours, document_parts, empty_copy};
+use super::geometry::{Contour, Point};
+
+/// Length of the moves between cuts in mm, starting from the origin in document order
+pub fn travel_distance(document: &Document) -> f32 {
+    let mut position: Point = (0.0, 0.0);
+    let mut travel = 0.0;
+    for contour in document_contours(document) {
+        if let (Some(start), Some(end)) = (contour.points.first(), contour.points.last()) {
+            travel += distance(&position, start);
+            position = if contour.closed { *start } else { *end };
+        }
+    }
+    travel
+}
+
+/// Reorder the cuts and start points of closed cuts to shorten the moves between them.
+///
+/// Next cut is always the nearest one that may be cut, holes of a part are cut before its
+/// outline, so the part doesn't move before all of it is cut. Open cuts may be reversed.
+pub fn optimize_toolpath(document: &Document) -> Document {
+    let mut contours: Vec<(Contour, usize, bool)> = vec![];
+    let mut holes_left = vec![];
+    for (index, part) in document_parts(document).into_iter().enumerate() {
+        holes_left.push(part.holes.len());
+        contours.extend(part.holes.into_iter().map(|hole| (hole, index, false)));
+        contours.push((part.outline, index, true));
+        contours.extend(part.joined.into_iter().map(|open| (open, index, true)));
+    }
+
+    let mut optimized = empty_copy(document);
+    let mut cut = vec![false; contours.len()];
+    let mut position: Point = (0.0, 0.0);
+    while let Some((index, start)) = contours
+        .iter()
+        .enumerate()
+        .filter(|(index, (_, part, outline))| !cut[*index] && (!outline || holes_left[*part] == 0))
+        .map(|(index, (contour, _, _))| (index, nearest_start(contour, &position)))
+        .min_by(|(_, a), (_, b)| distance(&position, &a.1).total_cmp(&distance(&position, &b.1)))
+        .map(|(index, (start, _))| (index, start))
+    {
+        let (contour, part, outline) = &contours[index];
+        let contour = start_at(contour, start);
+        position = if contour.closed {
+            contour.points[0]
+        } else {
+            contour.points[contour.points.len() - 1]
+        };
+        optimized.append(contour_path(&contour));
+
+        cut[index] = true;
+        if !outline {
+            holes_left[*part] -= 1;
+        }
+    }
+
+    optimized
+}
+
+/// Index of the point to start the cut from and the point, closest to `position`.
+///
+/// Closed contour can start at any point, open one at either end.
+fn nearest_start(contour: &Contour, position: &Point) -> (usize, Point) {
+    let last = contour.points.len() - 1;
+    let candidates = if contour.closed {
+        (0..=last).collect::<Vec<usize>>()
+    } else {
+        vec![0, last]
+    };
+
+    candidates
+        .into_iter()
+        .map(|index| (index, contour.points[index]))
+        .min_by(|(_, a), (_, b)| distance(position, a).total_cmp(&distance(position, b)))
+        .unwrap()
+}
+
+/// Same contour starting from point `start`, open contour starting from its end is reversed
+fn start_at(contour: &Contour, start: usize) -> Contour {
+    let mut points = contour.points.clone();
+    if contour.closed {
+        points.rotate_left(start);
+    } else if start != 0 {
+        points.reverse();
+    }
+
+    Contour {
+        points,
+        closed: contour.closed,
+        color: contour.color.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rack::{generate_layout_svg, Dimensions, Layout};
+
+    fn square(x: f32, y: f32, size: f32) -> Contour {
+        Contour {
+            points: vec![(x, y), (x + size, y), (x + size, y + size), (x, y + size)],
+            closed: true,
+            color: "black".to_string(),
+        }
+    }
+
+    /// Every hole comes before the outline of its part
+    fn holes_before_outlines(document: &Document) -> bool {
+        let contours = document_contours(document);
+        let position = |contour: &Contour| contours.iter().position(|cut| cut == contour).unwrap();
+        document_parts(document).iter().all(|part| {
+            part.holes
+                .iter()
+                .all(|hole| position(hole) < position(&part.outline))
+        })
+    }
+
+    #[test]
+    fn holes_are_cut_before_their_outline() {
+        let document = [
+            square(200.0, 200.0, 50.0),
+            square(0.0, 0.0, 100.0),
+            square(40.0, 40.0, 20.0),
+        ]
+        .iter()
+        .fold(Document::new(), |document, contour| {
+            document.add(contour_path(contour))
+        });
+        assert!(!holes_before_outlines(&document));
+
+        let optimized = optimize_toolpath(&document);
+
+        assert!(holes_before_outlines(&optimized));
+        let contours = document_contours(&optimized);
+        assert_eq!(contours[0].points[0], (40.0, 40.0));
+        assert_eq!(contours[1].points[0], (0.0, 0.0));
+        assert_eq!(contours[2].points[0], (200.0, 200.0));
+        assert!(travel_distance(&optimized) <= travel_distance(&document));
+    }
+
+    #[test]
+    fn optimized_rack_travels_less() {
+        let classic_1 = Dimensions {
+            width: 170,
+            depth: 210,
+            height: 56,
+            side_wing_from_box_top: 15,
+            side_wing_width: 8,
+        };
+        let document =
+            generate_layout_svg(&Layout::uniform(&classic_1, 3, 2), 4.0, "black", "blue").unwrap();
+
+        let optimized = optimize_toolpath(&document);
+
+        assert!(travel_distance(&optimized) <= travel_distance(&document));
+        assert!(holes_before_outlines(&optimized));
+        assert_eq!(
+            document_contours(&optimized).len(),
+            document_contours(&document).len()
+        );
+    }
+
+    #[test]
+    fn open_cut_is_started_from_the_nearer_end() {
+        let open = Contour {
+            closed: false,
+            ..square(0.0, 0.0, 10.0)
+        };
+        let (start, point) = nearest_start(&open, &(0.0, 12.0));
+        assert_eq!((start, point), (3, (0.0, 10.0)));
+        assert_eq!(
+            start_at(&open, start).points,
+            vec![(0.0, 10.0), (10.0, 10.0), (10.0, 0.0), (0.0, 0.0)]
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! JavaScript bindings, so the web UI can use the same geometry as the CLI.
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::rack::bom::{bill_of_materials, BomItem};
+use crate::rack::material::{material_presets, Material};
+use crate::rack::spec::RackSpec;
+use crate::rack::{generate_layout_svg, Container};
+use crate::supported_containers;
+
+/// Container with its key, as used in the rack spec
+#[derive(Serialize)]
+struct ContainerWithKey {
+    key: String,
+    #[serde(flatten)]
+    container: Container,
+}
+
+/// SVG of the rack and parts needed to build it
+#[derive(Serialize)]
+struct GeneratedRack {
+    svg: String,
+    bom: Vec<BomItem>,
+}
+
+/// List supported containers with their dimensions and links
+#[wasm_bindgen(js_name = supportedContainers)]
+pub fn supported_containers_js() -> Result<JsValue, JsError> {
+    let containers = supported_containers()
+        .into_iter()
+        .map(|container| ContainerWithKey {
+            key: container.key(),
+            container,
+        })
+        .collect::<Vec<ContainerWithKey>>();
+
+    Ok(serde_wasm_bindgen::to_value(&containers)?)
+}
+
+/// Generate rack with same rows and columns, returns `{ svg, bom }`
+#[wasm_bindgen(js_name = generateSvg)]
+pub fn generate_svg_js(
+    rows: usize,
+    columns: usize,
+    material_thickness: f32,
+    container: &str,
+    primary_color: &str,
+    secondary_color: &str,
+) -> Result<JsValue, JsError> {
+    generate(&RackSpec {
+        container: container.to_string(),
+        rows,
+        columns,
+        material_thickness,
+        primary_color: primary_color.to_string(),
+        secondary_color: secondary_color.to_string(),
+        ..RackSpec::default()
+    })
+}
+
+/// List material presets with measured thickness, kerf and fit clearance
+#[wasm_bindgen(js_name = materialPresets)]
+pub fn material_presets_js() -> Result<JsValue, JsError> {
+    Ok(serde_wasm_bindgen::to_value(&material_presets())?)
+}
+
+/// Generate rack from an object with same fields as `RackSpec`, returns `{ svg, bom }`
+#[wasm_bindgen(js_name = generateRack)]
+pub fn generate_rack_js(spec: JsValue) -> Result<JsValue, JsError> {
+    generate(&serde_wasm_bindgen::from_value(spec)?)
+}
+
+fn generate(spec: &RackSpec) -> Result<JsValue, JsError> {
+    let rack = generate_rack(spec).map_err(|error| JsError::new(&error.to_string()))?;
+    Ok(serde_wasm_bindgen::to_value(&rack)?)
+}
+
+/// Rack from the spec with the built-in containers and material presets
+fn generate_rack(spec: &RackSpec) -> Result<GeneratedRack, Box<dyn std::error::Error>> {
+    let material: Material = spec.material(&material_presets())?;
+    let layout = spec.layout(&supported_containers())?;
+    let svg = generate_layout_svg(
+        &layout,
+        &material,
+        &spec.primary_color,
+        &spec.secondary_color,
+    )?;
+
+    Ok(GeneratedRack {
+        svg: svg.to_string(),
+        bom: bill_of_materials(&layout, material.measured_thickness),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(container: &str) -> RackSpec {
+        RackSpec {
+            container: container.to_string(),
+            rows: 2,
+            columns: 3,
+            material_thickness: 4.0,
+            ..RackSpec::default()
+        }
+    }
+
+    #[test]
+    fn rack_has_svg_and_bill_of_materials() {
+        let rack = generate_rack(&spec("smartstore-classic_1")).unwrap();
+        assert!(rack.svg.starts_with("<svg"));
+        let panels = rack
+            .bom
+            .iter()
+            .find(|item| item.part == "Side panel")
+            .unwrap();
+        assert_eq!(panels.quantity, 4);
+    }
+
+    #[test]
+    fn errors_are_passed_with_their_message() {
+        let error = generate_rack(&spec("smartstore-classic_9")).err().unwrap();
+        assert!(error.to_string().contains("smartstore-classic_9"));
+
+        let error = generate_rack(&RackSpec {
+            columns: 0,
+            ..spec("smartstore-classic_1")
+        })
+        .err()
+        .unwrap();
+        assert_eq!(error.to_string(), "Rack needs at least one column");
+    }
+}